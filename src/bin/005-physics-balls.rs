@@ -2,7 +2,7 @@ use bevy::prelude::*;
 use bevy::render::mesh::PlaneMeshBuilder;
 use bevy_rapier3d::prelude::*;
 use wasm_bindgen::prelude::*;
-use web_demos::{player::PlayerPlugin, DefaultPluginsWithCustomWindow};
+use web_demos::{player::PlayerPlugin, time_control::TimeControlPlugin, DefaultPluginsWithCustomWindow};
 #[wasm_bindgen(js_name = demoName)]
 pub fn demo_name() -> String {
     "Physics: Ball Pit".to_string()
@@ -21,6 +21,7 @@ pub fn start_game() {
     App::new()
         .add_plugins(DefaultPluginsWithCustomWindow)
         .add_plugins(RapierPhysicsPlugin::<NoUserData>::default())
+        .add_plugins(TimeControlPlugin)
         .add_plugins(PlayerPlugin)
         .add_systems(Startup, setup)
         .add_systems(Update, respawn_balls)
@@ -56,7 +57,6 @@ fn setup(mut commands: Commands,
             shadows_enabled: true,
             shadow_depth_bias: 0.1,
             shadow_normal_bias: 0.1,
-            ..default()
         },
         transform: Transform::from_xyz(0.0, 50.0, 0.0)
             .looking_at(Vec3::ZERO, Vec3::Y),
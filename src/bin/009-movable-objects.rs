@@ -10,6 +10,7 @@ use bevy_rapier3d::prelude::*;
 use wasm_bindgen::prelude::*;
 use web_demos::{DefaultPluginsWithCustomWindow};
 use web_demos::player::PlayerPlugin;
+use web_demos::time_control::TimeControlPlugin;
 
 #[wasm_bindgen(js_name = sourceFile)]
 pub fn source_file() -> String { include_str!("009-movable-objects.rs").to_string() }
@@ -68,6 +69,7 @@ pub fn start_game() {
         .add_plugins(DefaultPluginsWithCustomWindow)
         .add_plugins(PlayerPlugin)
         .add_plugins(RapierPhysicsPlugin::<NoUserData>::default())
+        .add_plugins(TimeControlPlugin)
         .add_systems(Startup, setup)
         .add_systems(Update, (
             pickup_detection,
@@ -166,6 +168,7 @@ fn setup(
 
 // Detect pickup and release intents
 // System to detect pickup and release intentions
+#[allow(clippy::type_complexity)]
 fn pickup_detection(
     mut commands: Commands,
     mouse_button_input: Res<ButtonInput<MouseButton>>,
@@ -263,6 +266,7 @@ fn move_picked_object(
 }
 
 // System to update colors based on components
+#[allow(clippy::type_complexity)]
 fn update_object_colors(
     mut query: Query<(
         &mut Handle<StandardMaterial>,
@@ -5,23 +5,43 @@ use wasm_bindgen::prelude::*;
 use bevy::{
     app::App,
     asset::Assets,
-    audio::Volume,
     color::Color,
     math::Vec3,
     pbr::{PbrBundle, StandardMaterial},
     render::mesh::PlaneMeshBuilder,
 };
 use web_demos::overball::components::*;
+use web_demos::overball::accessibility::AccessibilityPlugin;
+use web_demos::overball::achievements::AchievementsPlugin;
+use web_demos::overball::audio::AudioManagerPlugin;
+use web_demos::overball::camera::{CameraPlugin, CameraRig};
+use web_demos::overball::checkpoints::{checkpoint_color, CheckpointPlugin};
 use web_demos::overball::constants::*;
+use web_demos::overball::death::DeathPlugin;
+use web_demos::overball::doors::DoorPlugin;
+use web_demos::overball::events::GameplayEventsPlugin;
 use web_demos::overball::game_over::GameOverPlugin;
 use web_demos::overball::game_ui::GameUIPlugin;
+use web_demos::overball::hazards::HazardPlugin;
+use web_demos::overball::high_scores::{name_entry_active, HighScorePlugin};
+use web_demos::overball::level::LevelData;
+use web_demos::overball::localization::LocalizationPlugin;
 use web_demos::overball::main_menu::MainMenuPlugin;
+use web_demos::overball::minimap::MinimapPlugin;
 use web_demos::overball::pause_menu::PauseMenuPlugin;
+use web_demos::overball::replay::ReplayPlugin;
 use web_demos::overball::resources::*;
+use web_demos::overball::run_timer::RunTimerPlugin;
+use web_demos::overball::save_game::SaveGamePlugin;
+use web_demos::overball::scoring::ScoringPlugin;
+use web_demos::overball::simulation::{InterpolatedTransform, SimulationPlugin, TickRate};
 use web_demos::overball::states::*;
 use web_demos::overball::systems::*;
+use web_demos::overball::tiles::TilePlugin;
 use web_demos::overball::victory::VictoryPlugin;
-use web_demos::{overball::game_ui::PopupMessage, DefaultPluginsWithCustomWindow};
+use web_demos::overball::widgets::WidgetPlugin;
+use web_demos::time_control::{TimeControlInputSet, TimeControlPlugin};
+use web_demos::DefaultPluginsWithCustomWindow;
 
 // DEBUG
 // use web_demos::overball::util::*;
@@ -48,14 +68,42 @@ fn main() {
 #[wasm_bindgen(js_name = startGame)]
 pub fn start_game() {
     let mut app = App::new();
+
+    // Step physics on a fixed tick so gameplay doesn't depend on the frame rate and runs
+    // can be replayed. Configured before the physics plugin so it never starts variable.
+    app.insert_resource(RapierConfiguration {
+        timestep_mode: TickRate::default().timestep_mode(),
+        ..RapierConfiguration::new(1.0)
+    });
+
     app
         // Plugins
-        .add_plugins(RapierPhysicsPlugin::<NoUserData>::default())
+        .add_plugins(RapierPhysicsPlugin::<NoUserData>::default().in_fixed_schedule())
         .add_plugins(DefaultPluginsWithCustomWindow)
         // My plugins
+        .add_plugins(AudioManagerPlugin)
+        .add_plugins(WidgetPlugin)
+        .add_plugins(LocalizationPlugin)
+        .add_plugins(AccessibilityPlugin)
+        .add_plugins(SimulationPlugin)
+        .add_plugins(GameplayEventsPlugin)
+        .add_plugins(TimeControlPlugin)
         .add_plugins(MainMenuPlugin)
         .add_plugins(PauseMenuPlugin)
         .add_plugins(GameUIPlugin)
+        .add_plugins(MinimapPlugin)
+        .add_plugins(TilePlugin)
+        .add_plugins(ScoringPlugin)
+        .add_plugins(DoorPlugin)
+        .add_plugins(HazardPlugin)
+        .add_plugins(CheckpointPlugin)
+        .add_plugins(DeathPlugin)
+        .add_plugins(CameraPlugin)
+        .add_plugins(RunTimerPlugin)
+        .add_plugins(HighScorePlugin)
+        .add_plugins(AchievementsPlugin)
+        .add_plugins(ReplayPlugin)
+        .add_plugins(SaveGamePlugin)
         .add_plugins(VictoryPlugin)
         .add_plugins(GameOverPlugin);
 
@@ -75,35 +123,27 @@ pub fn start_game() {
             check_assets_loaded.run_if(in_state(AppState::Loading)),
         )
         // Game state
+        .add_systems(OnEnter(AppState::Game), reset_transition)
         .add_systems(
-            OnEnter(AppState::Game),
+            OnEnter(InGameState::Reset),
             (
-                reset_transition,
-                setup_background_music.before(reset_transition),
+                clear_context.before(GameplaySet::Setup),
+                (setup_map, setup_player).in_set(GameplaySet::Setup),
             ),
         )
+        .add_systems(OnExit(InGameState::Playing), release_player_force)
         .add_systems(
-            OnEnter(InGameState::Reset),
-            (setup_map, setup_player, clear_context).in_set(GameplaySet::Setup),
-        )
-        .add_systems(
-            Update,
+            FixedUpdate,
             (
                 // Player
-                move_player_when_pressing_keys,
                 check_player_out_of_bounds,
-                // Door
-                handle_door_collisions,
-                update_door_movement,
                 // Tile
                 detect_ball_on_tile,
                 check_winning_tile,
             )
                 .in_set(GameplaySet::Update)
                 .run_if(in_state(InGameState::Playing)),
-        )
-        // Player Died
-        .add_systems(OnEnter(InGameState::PlayerDied), handle_player_death);
+        );
 
     #[cfg(target_arch = "x86_64")]
     app.add_plugins(WorldInspectorPlugin::new());
@@ -112,13 +152,26 @@ pub fn start_game() {
 }
 
 fn configure_system_sets(app: &mut App) {
+    // All gameplay runs on the fixed tick: input ahead of the physics step, rules after it
+    app.configure_sets(
+        FixedUpdate,
+        GameplaySet::Input
+            .run_if(in_state(AppState::Game))
+            .run_if(in_state(InGameState::Playing))
+            .run_if(no_pending_transition)
+            .before(PhysicsSet::SyncBackend),
+    );
     app.configure_sets(
-        Update,
-        (GameplaySet::Update
+        FixedUpdate,
+        GameplaySet::Update
             .run_if(in_state(AppState::Game))
-            .run_if(in_state(InGameState::Playing)),),
+            .run_if(in_state(InGameState::Playing))
+            .run_if(no_pending_transition)
+            .after(PhysicsSet::Writeback),
     );
     app.configure_sets(OnEnter(AppState::Game), GameplaySet::Setup);
+    // The debug time keys are letters like any other while typing a name
+    app.configure_sets(Update, TimeControlInputSet.run_if(not(name_entry_active)));
 }
 
 // Transition system to start game when we enter AppState::Game
@@ -130,6 +183,9 @@ fn setup_map(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
+    mut tile_assets: ResMut<TileAssets>,
+    mut tile_grid: ResMut<TileGrid>,
+    context: Res<GameContext>,
 ) {
     // Flat map
     commands
@@ -183,7 +239,8 @@ fn setup_map(
                 base_color: Color::srgb(0.0, 1.0, 0.0),
                 ..default()
             }),
-            transform: Transform::from_translation(Vec3::new(15.0, 0.1, 0.0)),
+            // Past the row of doors across the platform
+            transform: Transform::from_translation(Vec3::new(16.5, 0.1, 0.0)),
             ..default()
         },
         WinningTile,
@@ -191,46 +248,126 @@ fn setup_map(
     ));
 
     // Tiles
-    for x in -5..=5 {
-        for z in -5..=5 {
-            let position = Vec3::new(x as f32, 0.1, z as f32);
-            commands.spawn((
+    let level = LevelData::get(context.level);
+    tile_grid.clear();
+    for (position, kind) in level.tile_specs() {
+        let tile = commands
+            .spawn((
                 PbrBundle {
-                    mesh: meshes.add(PlaneMeshBuilder::from_length(0.5)),
-                    material: materials.add(StandardMaterial {
-                        base_color: Color::srgb(0.5, 0.5, 0.5),
-                        ..default()
-                    }),
+                    mesh: tile_assets.mesh.clone(),
+                    material: tile_assets.material(kind, false, &mut materials),
                     transform: Transform::from_translation(position),
                     ..default()
                 },
                 Tile {
                     position,
                     activated: false,
+                    kind,
                 },
                 GameMap,
-            ));
-        }
+            ))
+            .id();
+        tile_grid.insert(position, tile);
     }
 
-    // Door
-    commands.spawn((
-        PbrBundle {
-            mesh: meshes.add(Mesh::from(Cuboid::new(1.0, 1.0, 5.0))),
-            material: materials.add(StandardMaterial {
-                base_color: Color::srgb(0.0, 0.0, 1.0),
+    // Doors
+    for door in level.doors {
+        commands.spawn((
+            PbrBundle {
+                mesh: meshes.add(Mesh::from(Cuboid {
+                    half_size: door.half_size,
+                })),
+                material: materials.add(StandardMaterial {
+                    base_color: door.requirement.color(),
+                    ..default()
+                }),
+                transform: Transform::from_translation(door.position),
                 ..default()
-            }),
-            transform: Transform::from_translation(DOOR_POSITION),
-            ..default()
-        },
-        Collider::cuboid(0.5, 0.5, 2.5),
-        Door {
-            required_score: DOOR_REQUIRED_SCORE,
-            is_open: false,
-        },
-        GameMap,
-    ));
+            },
+            RigidBody::KinematicPositionBased,
+            Collider::cuboid(door.half_size.x, door.half_size.y, door.half_size.z),
+            Door {
+                requirement: door.requirement,
+                state: DoorState::Closed,
+                closed_position: door.position,
+                close_timer: door
+                    .close_after
+                    .map(|seconds| Timer::from_seconds(seconds, TimerMode::Once)),
+            },
+            InterpolatedTransform::default(),
+            GameMap,
+        ));
+    }
+
+    // Keys
+    for key in level.keys {
+        commands.spawn((
+            PbrBundle {
+                mesh: meshes.add(Mesh::from(Cuboid::new(0.3, 0.3, 0.3))),
+                material: materials.add(StandardMaterial {
+                    base_color: DoorRequirement::Key(key.id).color(),
+                    ..default()
+                }),
+                transform: Transform::from_translation(key.position),
+                ..default()
+            },
+            DoorKey { id: key.id },
+            GameMap,
+        ));
+    }
+
+    // Moving hazards
+    for hazard in level.hazards {
+        commands.spawn((
+            PbrBundle {
+                mesh: meshes.add(Mesh::from(Cuboid {
+                    half_size: hazard.half_size,
+                })),
+                material: materials.add(StandardMaterial {
+                    base_color: Color::srgb(0.8, 0.1, 0.1),
+                    emissive: LinearRgba::rgb(2.0, 0.0, 0.0),
+                    ..default()
+                }),
+                transform: Transform::from_translation(hazard.position),
+                ..default()
+            },
+            RigidBody::KinematicPositionBased,
+            Collider::cuboid(hazard.half_size.x, hazard.half_size.y, hazard.half_size.z),
+            Sensor,
+            MovingHazard {
+                motion: hazard.motion,
+                origin: hazard.position,
+                elapsed: 0.0,
+                next_waypoint: 0,
+            },
+            InterpolatedTransform::default(),
+            GameMap,
+        ));
+    }
+
+    // Checkpoints
+    for checkpoint in level.checkpoints {
+        let (base_color, emissive) = checkpoint_color(false);
+        commands.spawn((
+            PbrBundle {
+                mesh: meshes.add(Cylinder::new(CHECKPOINT_RADIUS, 0.05)),
+                material: materials.add(StandardMaterial {
+                    base_color,
+                    emissive,
+                    ..default()
+                }),
+                transform: Transform::from_translation(checkpoint.position + Vec3::Y * 0.03),
+                ..default()
+            },
+            Collider::cylinder(0.3, CHECKPOINT_RADIUS),
+            Sensor,
+            Checkpoint {
+                respawn_position: checkpoint.position + Vec3::Y * RESPAWN_HEIGHT,
+                activated: false,
+            },
+            GameMap,
+        ));
+    }
 
     // light
     commands.spawn((
@@ -260,8 +397,6 @@ fn setup_player(
         .spawn(PlayerBundle {
             player: Player,
             ball: Ball {
-                position: ball_properties.position,
-                velocity: ball_properties.velocity,
                 radius: ball_properties.radius,
             },
             scene_bundle: SceneBundle {
@@ -274,96 +409,31 @@ fn setup_player(
             },
             collider: Collider::ball(ball_properties.radius * 2.0),
             restitution: Restitution::coefficient(0.3),
+            friction: Friction::coefficient(BALL_FRICTION),
+            mass_properties: ColliderMassProperties::Mass(BALL_MASS),
             rigid_body: RigidBody::Dynamic,
+            velocity: Velocity::linear(ball_properties.velocity),
+            external_force: ExternalForce::default(),
+            external_impulse: ExternalImpulse::default(),
+            damping: Damping {
+                linear_damping: BALL_LINEAR_DAMPING,
+                angular_damping: BALL_ANGULAR_DAMPING,
+            },
         })
-        .insert(ActiveEvents::COLLISION_EVENTS);
+        .insert((ActiveEvents::COLLISION_EVENTS, InterpolatedTransform::default()));
 
-    // Player camera
+    // Player camera, starting on the whole level before it settles on the ball
+    let camera_position = Vec3::new(0.0, CAMERA_TOP_DOWN_HEIGHT, 0.0);
     commands.spawn((
         Camera3dBundle {
-            transform: Transform::from_xyz(0.0, 40.0, 0.0).looking_at(Vec3::ZERO, Vec3::NEG_Z),
+            transform: Transform::from_translation(camera_position)
+                .looking_at(Vec3::ZERO, Vec3::NEG_Z),
             ..default()
         },
+        CameraRig::new(camera_position, Vec3::ZERO),
         PlayerCamera,
     ));
 
     game_state.set(AppState::Game);
     gameplay_state.set(InGameState::Playing);
 }
-
-#[derive(Component)]
-struct DoorMovement {
-    speed: f32,
-}
-
-fn handle_door_collisions(
-    mut commands: Commands,
-    mut collision_events: EventReader<CollisionEvent>,
-    context: Res<GameContext>,
-    mut ball_query: Query<(&mut Ball, &Transform), With<Player>>,
-    mut door_query: Query<(Entity, &Door)>,
-    asset_server: Res<AssetServer>,
-    audio_assets: Res<AudioAssets>,
-) {
-    for collision_event in collision_events.read() {
-        if let CollisionEvent::Started(entity1, entity2, _) = collision_event {
-            if let Ok((mut ball, ball_transform)) = ball_query.get_mut(*entity2) {
-                if let Ok((door_entity, door)) = door_query.get_mut(*entity1) {
-                    ball.velocity = Vec3::ZERO;
-                    debug!(
-                        "Ball collided with door at position: {:?}",
-                        ball_transform.translation
-                    );
-                    // Door thunk sound
-                    commands.spawn(AudioBundle {
-                        source: audio_assets.door_thunk_sound.clone(),
-                        settings: PlaybackSettings {
-                            volume: Volume::new(0.2),
-                            ..default()
-                        },
-                    });
-
-                    // Check if the player has the required score to open the door
-                    if context.score >= door.required_score {
-                        commands.spawn(AudioBundle {
-                            source: audio_assets.door_opening_sound.clone(),
-                            settings: PlaybackSettings {
-                                volume: Volume::new(0.2),
-                                ..default()
-                            },
-                        });
-
-                        commands
-                            .entity(door_entity)
-                            .insert(DoorMovement { speed: 0.5 });
-                    } else {
-                        PopupMessage::spawn(
-                            &mut commands,
-                            &asset_server,
-                            &format!(
-                                "You need a score of {} to open this door",
-                                door.required_score
-                            ),
-                            3.0,
-                        );
-                    }
-                }
-            }
-        }
-    }
-}
-
-fn update_door_movement(
-    mut commands: Commands,
-    time: Res<Time>,
-    mut query: Query<(Entity, &mut Transform, &DoorMovement)>,
-) {
-    for (entity, mut transform, door_movement) in query.iter_mut() {
-        transform.translation.y -= door_movement.speed * time.delta_seconds();
-        if transform.translation.y < 0.0 {
-            commands.entity(entity).despawn_recursive();
-        }
-    }
-}
-
-
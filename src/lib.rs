@@ -5,6 +5,7 @@ use wasm_bindgen::prelude::wasm_bindgen;
 
 pub mod player;
 pub mod overball;
+pub mod time_control;
 
 pub fn get_window() -> Window {
     Window {
@@ -0,0 +1,421 @@
+// Accessibility options: colorblind-safe palettes with markers on activated tiles, a UI scale,
+// reduced motion and a high-contrast theme
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::components::{Door, DoorKey, DoorRequirement, Tile, TileKind};
+use super::localization::LocalizedText;
+use super::resources::TileAssets;
+use super::storage;
+use super::widgets::{MenuBuilder, Toggle, UiTheme, WidgetActivated, WidgetSet};
+
+const ACCESSIBILITY_SETTINGS_KEY: &str = "accessibility_settings";
+// Sizes the UI scale button cycles through
+const UI_SCALES: [f32; 4] = [0.75, 1.0, 1.25, 1.5];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ColorPalette {
+    #[default]
+    Standard,
+    // Red-green safe, built on the Okabe-Ito colors
+    Deuteranopia,
+    // Blue-yellow safe, leaning on red, teal and lightness
+    Tritanopia,
+}
+
+impl ColorPalette {
+    const ALL: [ColorPalette; 3] = [
+        ColorPalette::Standard,
+        ColorPalette::Deuteranopia,
+        ColorPalette::Tritanopia,
+    ];
+
+    fn next(self) -> ColorPalette {
+        let index = ColorPalette::ALL
+            .iter()
+            .position(|palette| *palette == self);
+        ColorPalette::ALL[index.map_or(0, |index| (index + 1) % ColorPalette::ALL.len())]
+    }
+
+    fn key(&self) -> &'static str {
+        match self {
+            ColorPalette::Standard => "accessibility.palette_standard",
+            ColorPalette::Deuteranopia => "accessibility.palette_deuteranopia",
+            ColorPalette::Tritanopia => "accessibility.palette_tritanopia",
+        }
+    }
+
+    pub fn tile_color(&self, kind: TileKind, activated: bool) -> Color {
+        let (normal, activated_color) = match self {
+            ColorPalette::Standard => {
+                return if activated {
+                    kind.activated_color()
+                } else {
+                    kind.color()
+                }
+            }
+            ColorPalette::Deuteranopia => match kind {
+                TileKind::Normal => (Color::srgb(0.5, 0.5, 0.5), Color::srgb(0.0, 0.45, 0.7)),
+                TileKind::Boost { .. } => {
+                    (Color::srgb(0.95, 0.9, 0.25), Color::srgb(1.0, 1.0, 0.65))
+                }
+                TileKind::Ice => (Color::srgb(0.55, 0.8, 0.95), Color::srgb(0.9, 0.97, 1.0)),
+                TileKind::Crumbling => (Color::srgb(0.35, 0.3, 0.25), Color::srgb(0.6, 0.55, 0.5)),
+                TileKind::Teleporter { .. } => {
+                    (Color::srgb(0.8, 0.6, 0.7), Color::srgb(0.95, 0.85, 0.9))
+                }
+                TileKind::Hazard => (Color::srgb(0.85, 0.35, 0.0), Color::srgb(0.85, 0.35, 0.0)),
+                TileKind::Switch { .. } => {
+                    (Color::srgb(0.0, 0.6, 0.5), Color::srgb(0.4, 0.85, 0.75))
+                }
+            },
+            ColorPalette::Tritanopia => match kind {
+                TileKind::Normal => (Color::srgb(0.5, 0.5, 0.5), Color::srgb(0.0, 0.7, 0.7)),
+                TileKind::Boost { .. } => {
+                    (Color::srgb(0.95, 0.45, 0.55), Color::srgb(1.0, 0.75, 0.8))
+                }
+                TileKind::Ice => (Color::srgb(0.85, 0.85, 0.85), Color::srgb(1.0, 1.0, 1.0)),
+                TileKind::Crumbling => (Color::srgb(0.3, 0.2, 0.2), Color::srgb(0.5, 0.4, 0.4)),
+                TileKind::Teleporter { .. } => {
+                    (Color::srgb(0.55, 0.0, 0.25), Color::srgb(0.75, 0.3, 0.45))
+                }
+                TileKind::Hazard => (Color::srgb(0.85, 0.0, 0.0), Color::srgb(0.85, 0.0, 0.0)),
+                TileKind::Switch { .. } => {
+                    (Color::srgb(0.0, 0.35, 0.4), Color::srgb(0.2, 0.5, 0.55))
+                }
+            },
+        };
+        if activated {
+            activated_color
+        } else {
+            normal
+        }
+    }
+
+    // Keys share the color of the doors they open
+    pub fn door_color(&self, requirement: &DoorRequirement) -> Color {
+        match (self, requirement) {
+            (ColorPalette::Standard, _) => requirement.color(),
+            (ColorPalette::Deuteranopia, DoorRequirement::Score(_)) => Color::srgb(0.0, 0.45, 0.7),
+            (ColorPalette::Deuteranopia, DoorRequirement::Region { .. }) => {
+                Color::srgb(0.35, 0.7, 0.9)
+            }
+            (ColorPalette::Deuteranopia, DoorRequirement::Key(_)) => Color::srgb(0.95, 0.9, 0.25),
+            (ColorPalette::Deuteranopia, DoorRequirement::Switch(_)) => Color::srgb(0.0, 0.6, 0.5),
+            (ColorPalette::Tritanopia, DoorRequirement::Score(_)) => Color::srgb(0.0, 0.7, 0.7),
+            (ColorPalette::Tritanopia, DoorRequirement::Region { .. }) => {
+                Color::srgb(0.95, 0.45, 0.55)
+            }
+            (ColorPalette::Tritanopia, DoorRequirement::Key(_)) => Color::srgb(1.0, 1.0, 1.0),
+            (ColorPalette::Tritanopia, DoorRequirement::Switch(_)) => Color::srgb(0.0, 0.35, 0.4),
+        }
+    }
+}
+
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct AccessibilitySettings {
+    pub palette: ColorPalette,
+    // Marks activated tiles with a diamond, so they don't rely on color alone
+    pub tile_markers: bool,
+    // Multiplier on every UI size, text included
+    pub ui_scale: f32,
+    // No camera shake or flashing
+    pub reduced_motion: bool,
+    pub high_contrast: bool,
+}
+
+impl Default for AccessibilitySettings {
+    fn default() -> Self {
+        AccessibilitySettings {
+            palette: ColorPalette::default(),
+            tile_markers: false,
+            ui_scale: 1.0,
+            reduced_motion: false,
+            high_contrast: false,
+        }
+    }
+}
+
+impl AccessibilitySettings {
+    fn next_ui_scale(&self) -> f32 {
+        let index = UI_SCALES.iter().position(|scale| *scale >= self.ui_scale);
+        UI_SCALES[index.map_or(0, |index| (index + 1) % UI_SCALES.len())]
+    }
+}
+
+// Mesh and material shared by every activated tile marker
+#[derive(Resource)]
+struct TileMarkerAssets {
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+}
+
+impl FromWorld for TileMarkerAssets {
+    fn from_world(world: &mut World) -> Self {
+        let mesh = world
+            .resource_mut::<Assets<Mesh>>()
+            .add(Plane3d::default().mesh().size(0.2, 0.2));
+        let material = world
+            .resource_mut::<Assets<StandardMaterial>>()
+            .add(StandardMaterial {
+                base_color: Color::srgb(0.1, 0.1, 0.1),
+                unlit: true,
+                ..default()
+            });
+        TileMarkerAssets { mesh, material }
+    }
+}
+
+#[derive(Component)]
+struct TileMarker;
+
+// Tiles that already have their marker
+#[derive(Component)]
+struct MarkedTile;
+
+#[derive(Component, Clone, Copy)]
+enum AccessibilityButton {
+    Palette,
+    UiScale,
+}
+
+impl AccessibilityButton {
+    fn label(&self, settings: &AccessibilitySettings) -> LocalizedText {
+        match self {
+            AccessibilityButton::Palette => LocalizedText::new(settings.palette.key()),
+            AccessibilityButton::UiScale => LocalizedText::new("accessibility.ui_scale")
+                .with_arg("value", (settings.ui_scale * 100.0).round()),
+        }
+    }
+}
+
+#[derive(Component, Clone, Copy)]
+enum AccessibilityToggle {
+    TileMarkers,
+    ReducedMotion,
+    HighContrast,
+}
+
+impl AccessibilityToggle {
+    fn is_on(&self, settings: &AccessibilitySettings) -> bool {
+        match self {
+            AccessibilityToggle::TileMarkers => settings.tile_markers,
+            AccessibilityToggle::ReducedMotion => settings.reduced_motion,
+            AccessibilityToggle::HighContrast => settings.high_contrast,
+        }
+    }
+
+    fn set(&self, settings: &mut AccessibilitySettings, on: bool) {
+        match self {
+            AccessibilityToggle::TileMarkers => settings.tile_markers = on,
+            AccessibilityToggle::ReducedMotion => settings.reduced_motion = on,
+            AccessibilityToggle::HighContrast => settings.high_contrast = on,
+        }
+    }
+}
+
+pub struct AccessibilityPlugin;
+
+impl Plugin for AccessibilityPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(
+            storage::load::<AccessibilitySettings>(ACCESSIBILITY_SETTINGS_KEY).unwrap_or_default(),
+        )
+        .init_resource::<TileAssets>()
+        .init_resource::<TileMarkerAssets>()
+        .add_systems(
+            Update,
+            (
+                (button_input, toggle_input).after(WidgetSet),
+                (
+                    save_accessibility_settings,
+                    apply_ui_scale,
+                    apply_theme,
+                    apply_tile_palette,
+                    show_tile_markers,
+                    update_accessibility_controls,
+                )
+                    .run_if(resource_changed::<AccessibilitySettings>),
+                (color_doors_and_keys, add_tile_markers),
+            )
+                .chain(),
+        );
+    }
+}
+
+// Accessibility controls for menus to embed
+pub fn spawn_accessibility_controls(menu: &mut MenuBuilder, settings: &AccessibilitySettings) {
+    for button in [AccessibilityButton::Palette, AccessibilityButton::UiScale] {
+        menu.button(button.label(settings), button);
+    }
+    let toggles = [
+        (
+            AccessibilityToggle::TileMarkers,
+            settings.tile_markers,
+            "accessibility.tile_markers_on",
+            "accessibility.tile_markers_off",
+        ),
+        (
+            AccessibilityToggle::ReducedMotion,
+            settings.reduced_motion,
+            "accessibility.reduced_motion_on",
+            "accessibility.reduced_motion_off",
+        ),
+        (
+            AccessibilityToggle::HighContrast,
+            settings.high_contrast,
+            "accessibility.high_contrast_on",
+            "accessibility.high_contrast_off",
+        ),
+    ];
+    for (toggle, on, on_key, off_key) in toggles {
+        menu.toggle(on, on_key, off_key, toggle);
+    }
+}
+
+fn button_input(
+    mut settings: ResMut<AccessibilitySettings>,
+    mut activated: EventReader<WidgetActivated>,
+    button_query: Query<&AccessibilityButton>,
+) {
+    for WidgetActivated(entity) in activated.read() {
+        match button_query.get(*entity) {
+            Ok(AccessibilityButton::Palette) => settings.palette = settings.palette.next(),
+            Ok(AccessibilityButton::UiScale) => settings.ui_scale = settings.next_ui_scale(),
+            Err(_) => {}
+        }
+    }
+}
+
+fn toggle_input(
+    mut settings: ResMut<AccessibilitySettings>,
+    mut activated: EventReader<WidgetActivated>,
+    toggle_query: Query<(&Toggle, &AccessibilityToggle)>,
+) {
+    for WidgetActivated(entity) in activated.read() {
+        if let Ok((toggle, setting)) = toggle_query.get(*entity) {
+            setting.set(&mut settings, toggle.on);
+        }
+    }
+}
+
+fn save_accessibility_settings(settings: Res<AccessibilitySettings>) {
+    storage::save(ACCESSIBILITY_SETTINGS_KEY, &*settings);
+}
+
+// Bevy's UI scale covers every node and text size, so no `TextStyle` needs touching
+fn apply_ui_scale(settings: Res<AccessibilitySettings>, mut ui_scale: ResMut<UiScale>) {
+    if ui_scale.0 != settings.ui_scale {
+        ui_scale.0 = settings.ui_scale;
+    }
+}
+
+fn apply_theme(settings: Res<AccessibilitySettings>, mut theme: ResMut<UiTheme>) {
+    let font = theme.font.clone();
+    *theme = if settings.high_contrast {
+        UiTheme::high_contrast(font)
+    } else {
+        UiTheme::standard(font)
+    };
+}
+
+fn apply_tile_palette(
+    settings: Res<AccessibilitySettings>,
+    mut tile_assets: ResMut<TileAssets>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    tile_assets.set_palette(settings.palette, &mut materials);
+}
+
+// Doors and keys each have their own material, colored when spawned and when the palette changes
+fn color_doors_and_keys(
+    settings: Res<AccessibilitySettings>,
+    door_query: Query<(Ref<Door>, &Handle<StandardMaterial>)>,
+    key_query: Query<(Ref<DoorKey>, &Handle<StandardMaterial>)>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let doors = door_query
+        .iter()
+        .filter(|(door, _)| settings.is_changed() || door.is_added())
+        .map(|(door, handle)| (door.requirement, handle));
+    let keys = key_query
+        .iter()
+        .filter(|(key, _)| settings.is_changed() || key.is_added())
+        .map(|(key, handle)| (DoorRequirement::Key(key.id), handle));
+
+    for (requirement, handle) in doors.chain(keys) {
+        if let Some(material) = materials.get_mut(handle) {
+            material.base_color = settings.palette.door_color(&requirement);
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn add_tile_markers(
+    mut commands: Commands,
+    settings: Res<AccessibilitySettings>,
+    marker_assets: Res<TileMarkerAssets>,
+    tile_query: Query<(Entity, &Tile), (Changed<Tile>, Without<MarkedTile>)>,
+) {
+    for (entity, tile) in tile_query.iter() {
+        if !tile.activated {
+            continue;
+        }
+        commands
+            .entity(entity)
+            .insert(MarkedTile)
+            .with_children(|parent| {
+                // A diamond just above the tile
+                parent.spawn((
+                    PbrBundle {
+                        mesh: marker_assets.mesh.clone(),
+                        material: marker_assets.material.clone(),
+                        transform: Transform::from_xyz(0.0, 0.01, 0.0)
+                            .with_rotation(Quat::from_rotation_y(std::f32::consts::FRAC_PI_4)),
+                        visibility: marker_visibility(&settings),
+                        ..default()
+                    },
+                    TileMarker,
+                ));
+            });
+    }
+}
+
+fn marker_visibility(settings: &AccessibilitySettings) -> Visibility {
+    if settings.tile_markers {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    }
+}
+
+fn show_tile_markers(
+    settings: Res<AccessibilitySettings>,
+    mut marker_query: Query<&mut Visibility, With<TileMarker>>,
+) {
+    for mut visibility in marker_query.iter_mut() {
+        visibility.set_if_neq(marker_visibility(&settings));
+    }
+}
+
+// Keeps the controls in step with the settings, e.g. after cycling a button
+fn update_accessibility_controls(
+    settings: Res<AccessibilitySettings>,
+    button_query: Query<(&AccessibilityButton, &Children)>,
+    mut text_query: Query<&mut LocalizedText>,
+    mut toggle_query: Query<(&mut Toggle, &AccessibilityToggle)>,
+) {
+    for (button, children) in button_query.iter() {
+        let mut texts = text_query.iter_many_mut(children);
+        while let Some(mut text) = texts.fetch_next() {
+            *text = button.label(&settings);
+        }
+    }
+    for (mut toggle, setting) in toggle_query.iter_mut() {
+        let on = setting.is_on(&settings);
+        if toggle.on != on {
+            toggle.on = on;
+        }
+    }
+}
@@ -0,0 +1,253 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::components::Tile;
+use super::events::{DoorBlocked, TileActivated};
+use super::game_ui::PopupMessage;
+use super::level::Medal;
+use super::localization::LocalizedText;
+use super::replay::ReplayState;
+use super::resources::{GameContext, RunTimer};
+use super::states::InGameState;
+use super::storage;
+use super::widgets::MenuBuilder;
+
+const ACHIEVEMENTS_KEY: &str = "achievements";
+const ACHIEVEMENT_TOAST_TIME: f32 = 3.0;
+
+// What counts towards an achievement
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AchievementGoal {
+    CompleteLevel,
+    // Every tile in the level that can be activated
+    ActivateAllTiles,
+    CompleteWithoutDying,
+    // Seconds on the run timer
+    CompleteUnder(f32),
+    // Counted across every run
+    BumpLockedDoors,
+}
+
+pub struct AchievementData {
+    // Stable id for the saved progress
+    pub id: &'static str,
+    // Localization keys
+    pub name: &'static str,
+    pub description: &'static str,
+    pub goal: AchievementGoal,
+    // Progress needed to unlock it
+    pub target: u32,
+}
+
+pub const ACHIEVEMENTS: &[AchievementData] = &[
+    AchievementData {
+        id: "first_win",
+        name: "achievements.first_win",
+        description: "achievements.first_win_description",
+        goal: AchievementGoal::CompleteLevel,
+        target: 1,
+    },
+    AchievementData {
+        id: "all_tiles",
+        name: "achievements.all_tiles",
+        description: "achievements.all_tiles_description",
+        goal: AchievementGoal::ActivateAllTiles,
+        target: 1,
+    },
+    AchievementData {
+        id: "flawless",
+        name: "achievements.flawless",
+        description: "achievements.flawless_description",
+        goal: AchievementGoal::CompleteWithoutDying,
+        target: 1,
+    },
+    AchievementData {
+        id: "speedrun",
+        name: "achievements.speedrun",
+        description: "achievements.speedrun_description",
+        goal: AchievementGoal::CompleteUnder(30.0),
+        target: 1,
+    },
+    AchievementData {
+        id: "door_knocker",
+        name: "achievements.door_knocker",
+        description: "achievements.door_knocker_description",
+        goal: AchievementGoal::BumpLockedDoors,
+        target: 10,
+    },
+];
+
+// Progress towards every achievement, persisted between sessions
+#[derive(Resource, Serialize, Deserialize, Default, Debug)]
+pub struct AchievementProgress {
+    progress: BTreeMap<String, u32>,
+    unlocked: BTreeSet<String>,
+}
+
+impl AchievementProgress {
+    pub fn progress(&self, achievement: &AchievementData) -> u32 {
+        self.progress
+            .get(achievement.id)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn is_unlocked(&self, achievement: &AchievementData) -> bool {
+        self.unlocked.contains(achievement.id)
+    }
+
+    // Returns true if this unlocked the achievement
+    pub fn advance(&mut self, achievement: &AchievementData, amount: u32) -> bool {
+        if self.is_unlocked(achievement) {
+            return false;
+        }
+        let progress = self.progress.entry(achievement.id.to_string()).or_default();
+        *progress = (*progress + amount).min(achievement.target);
+        if *progress < achievement.target {
+            return false;
+        }
+        self.unlocked.insert(achievement.id.to_string())
+    }
+
+    pub fn save(&self) {
+        storage::save(ACHIEVEMENTS_KEY, self);
+    }
+}
+
+// Tracks the achievements from gameplay events and shows a toast when one unlocks
+pub struct AchievementsPlugin;
+
+impl Plugin for AchievementsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(
+            storage::load::<AchievementProgress>(ACHIEVEMENTS_KEY).unwrap_or_default(),
+        )
+        .add_systems(Update, track_achievements)
+        // Only a run that ends in victory completes the level, even if the ball also died
+        // on the tick it reached the goal
+        .add_systems(OnEnter(InGameState::Victory), award_completion_achievements);
+    }
+}
+
+fn track_achievements(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    replay_state: Res<ReplayState>,
+    tile_query: Query<&Tile>,
+    mut tile_activated: EventReader<TileActivated>,
+    mut door_blocked: EventReader<DoorBlocked>,
+    mut progress: ResMut<AchievementProgress>,
+) {
+    let tiles_activated = tile_activated.read().count() > 0;
+    let doors_blocked = door_blocked.read().count() as u32;
+    // A replay repeats a run that already counted
+    if replay_state.is_playback() {
+        return;
+    }
+
+    let all_tiles_activated = tiles_activated
+        && tile_query
+            .iter()
+            .filter(|tile| tile.kind.activates())
+            .all(|tile| tile.activated);
+
+    advance_achievements(
+        &mut commands,
+        &asset_server,
+        &mut progress,
+        |goal| match goal {
+            AchievementGoal::ActivateAllTiles => u32::from(all_tiles_activated),
+            AchievementGoal::BumpLockedDoors => doors_blocked,
+            _ => 0,
+        },
+    );
+}
+
+fn award_completion_achievements(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    replay_state: Res<ReplayState>,
+    context: Res<GameContext>,
+    run_timer: Res<RunTimer>,
+    mut progress: ResMut<AchievementProgress>,
+) {
+    if replay_state.is_playback() {
+        return;
+    }
+
+    advance_achievements(
+        &mut commands,
+        &asset_server,
+        &mut progress,
+        |goal| match goal {
+            AchievementGoal::CompleteLevel => 1,
+            AchievementGoal::CompleteWithoutDying => u32::from(context.deaths == 0),
+            AchievementGoal::CompleteUnder(seconds) => u32::from(run_timer.elapsed < seconds),
+            _ => 0,
+        },
+    );
+}
+
+// Advances each achievement by the progress `amount` gives its goal, with a toast for the
+// ones that unlock
+fn advance_achievements(
+    commands: &mut Commands,
+    asset_server: &Res<AssetServer>,
+    progress: &mut AchievementProgress,
+    amount: impl Fn(AchievementGoal) -> u32,
+) {
+    let mut changed = false;
+    for achievement in ACHIEVEMENTS {
+        let amount = amount(achievement.goal);
+        if amount == 0 || progress.is_unlocked(achievement) {
+            continue;
+        }
+
+        changed = true;
+        if progress.advance(achievement, amount) {
+            PopupMessage::spawn(
+                commands,
+                asset_server,
+                LocalizedText::new("achievements.unlocked")
+                    .with_text_arg("name", LocalizedText::new(achievement.name)),
+                ACHIEVEMENT_TOAST_TIME,
+            );
+        }
+    }
+
+    if changed {
+        progress.save();
+    }
+}
+
+// Every achievement with its description, and the progress towards the ones with a count
+pub fn spawn_achievement_gallery(menu: &mut MenuBuilder, progress: &AchievementProgress) {
+    menu.title("achievements.title");
+
+    for achievement in ACHIEVEMENTS {
+        let unlocked = progress.is_unlocked(achievement);
+        let name_style = TextStyle {
+            color: if unlocked {
+                Medal::Gold.color()
+            } else {
+                Color::srgb(0.5, 0.5, 0.5)
+            },
+            ..menu.theme().text_style()
+        };
+        let status = if unlocked {
+            LocalizedText::new("achievements.status_unlocked")
+        } else if achievement.target > 1 {
+            LocalizedText::new("achievements.status_progress")
+                .with_arg("progress", progress.progress(achievement))
+                .with_arg("target", achievement.target)
+        } else {
+            LocalizedText::new("achievements.status_locked")
+        };
+
+        menu.text(LocalizedText::new(achievement.name), name_style, ());
+        menu.label(achievement.description, ());
+        menu.label(status, ());
+    }
+}
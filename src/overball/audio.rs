@@ -0,0 +1,328 @@
+use bevy::audio::{PlaybackMode, Volume};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::events::{DoorBumped, DoorClosed, DoorOpened, PlayerDied};
+use super::high_scores::name_entry_active;
+use super::resources::AudioAssets;
+use super::states::{AppState, InGameState};
+use super::storage;
+use super::widgets::{MenuBuilder, Slider, SliderChanged, Toggle, WidgetActivated, WidgetSet};
+
+const AUDIO_SETTINGS_KEY: &str = "audio_settings";
+// Music level while the game isn't being played, e.g. paused
+const MUSIC_DUCK_LEVEL: f32 = 0.3;
+// How fast the music fades to and from the ducked level, per second
+const MUSIC_DUCK_SPEED: f32 = 2.0;
+// How far the arrow keys move a volume slider
+const VOLUME_STEP: f32 = 0.05;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AudioBus {
+    Music,
+    Sfx,
+    Ui,
+}
+
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct AudioSettings {
+    pub master: f32,
+    pub music: f32,
+    pub sfx: f32,
+    pub ui: f32,
+    pub muted: bool,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        AudioSettings {
+            master: 0.5,
+            music: 0.4,
+            sfx: 0.4,
+            ui: 0.4,
+            muted: false,
+        }
+    }
+}
+
+impl AudioSettings {
+    pub fn volume(&self, bus: AudioBus) -> f32 {
+        if self.muted {
+            return 0.0;
+        }
+        self.master * self.level(Some(bus))
+    }
+
+    // `None` is the master level
+    pub fn level(&self, bus: Option<AudioBus>) -> f32 {
+        match bus {
+            None => self.master,
+            Some(AudioBus::Music) => self.music,
+            Some(AudioBus::Sfx) => self.sfx,
+            Some(AudioBus::Ui) => self.ui,
+        }
+    }
+
+    pub fn set_level(&mut self, bus: Option<AudioBus>, level: f32) {
+        let level = level.clamp(0.0, 1.0);
+        match bus {
+            None => self.master = level,
+            Some(AudioBus::Music) => self.music = level,
+            Some(AudioBus::Sfx) => self.sfx = level,
+            Some(AudioBus::Ui) => self.ui = level,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sfx {
+    DoorThunk,
+    DoorOpening,
+    Death,
+    GameOver,
+    Victory,
+    Click,
+}
+
+impl Sfx {
+    fn bus(&self) -> AudioBus {
+        match self {
+            Sfx::Click => AudioBus::Ui,
+            _ => AudioBus::Sfx,
+        }
+    }
+
+    fn source(&self, audio_assets: &AudioAssets) -> Handle<AudioSource> {
+        match self {
+            Sfx::DoorThunk => audio_assets.door_thunk_sound.clone(),
+            Sfx::DoorOpening => audio_assets.door_opening_sound.clone(),
+            Sfx::Death => audio_assets.death_sound.clone(),
+            Sfx::GameOver => audio_assets.game_over_sound.clone(),
+            Sfx::Victory => audio_assets.victory_sound.clone(),
+            Sfx::Click => audio_assets.click_sound.clone(),
+        }
+    }
+}
+
+// Plays a one-shot sound on its bus
+#[derive(Event, Debug, Clone, Copy)]
+pub struct PlaySfx(pub Sfx);
+
+#[derive(Component)]
+struct SoundEmitter(AudioBus);
+
+#[derive(Component)]
+struct Music;
+
+// Current duck multiplier for the music
+#[derive(Resource)]
+struct MusicDuck(f32);
+
+#[derive(Component)]
+struct VolumeSlider(Option<AudioBus>);
+
+#[derive(Component)]
+struct MuteToggle;
+
+// Owns every sound: music with pause ducking, one-shot effects through `PlaySfx`, and
+// persisted volume settings per bus
+pub struct AudioManagerPlugin;
+
+impl Plugin for AudioManagerPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(storage::load::<AudioSettings>(AUDIO_SETTINGS_KEY).unwrap_or_default())
+            .insert_resource(MusicDuck(1.0))
+            .add_event::<PlaySfx>()
+            .add_systems(OnEnter(AppState::Game), start_music)
+            .add_systems(OnExit(AppState::Game), stop_music)
+            .add_systems(
+                Update,
+                (
+                    gameplay_sfx,
+                    play_sfx.run_if(resource_exists::<AudioAssets>),
+                    duck_music,
+                    (apply_bus_volumes, save_audio_settings)
+                        .run_if(resource_changed::<AudioSettings>),
+                    (
+                        volume_slider_input,
+                        mute_toggle_input,
+                        // M is a letter like any other while typing a name
+                        toggle_mute_key.run_if(not(name_entry_active)),
+                    )
+                        .after(WidgetSet),
+                    update_volume_controls,
+                )
+                    .chain(),
+            );
+    }
+}
+
+fn start_music(
+    mut commands: Commands,
+    audio_assets: Res<AudioAssets>,
+    settings: Res<AudioSettings>,
+    music_query: Query<(), With<Music>>,
+) {
+    if !music_query.is_empty() {
+        return;
+    }
+    commands.spawn((
+        AudioBundle {
+            source: audio_assets.bg_music.clone(),
+            settings: PlaybackSettings {
+                mode: PlaybackMode::Loop,
+                volume: Volume::new(settings.volume(AudioBus::Music)),
+                ..default()
+            },
+        },
+        SoundEmitter(AudioBus::Music),
+        Music,
+    ));
+}
+
+fn stop_music(mut commands: Commands, music_query: Query<Entity, With<Music>>) {
+    for entity in music_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn play_sfx(
+    mut commands: Commands,
+    mut events: EventReader<PlaySfx>,
+    audio_assets: Res<AudioAssets>,
+    settings: Res<AudioSettings>,
+) {
+    for PlaySfx(sound) in events.read() {
+        let bus = sound.bus();
+        // Emitters despawn themselves once the sound finishes
+        commands.spawn((
+            AudioBundle {
+                source: sound.source(&audio_assets),
+                settings: PlaybackSettings::DESPAWN.with_volume(Volume::new(settings.volume(bus))),
+            },
+            SoundEmitter(bus),
+        ));
+    }
+}
+
+// Sounds for what happens in a run, at most one of each kind a frame
+fn gameplay_sfx(
+    mut door_bumped: EventReader<DoorBumped>,
+    mut door_opened: EventReader<DoorOpened>,
+    mut door_closed: EventReader<DoorClosed>,
+    mut player_died: EventReader<PlayerDied>,
+    mut play_sfx: EventWriter<PlaySfx>,
+) {
+    if door_bumped.read().count() > 0 {
+        play_sfx.send(PlaySfx(Sfx::DoorThunk));
+    }
+    if door_opened.read().count() + door_closed.read().count() > 0 {
+        play_sfx.send(PlaySfx(Sfx::DoorOpening));
+    }
+    if player_died.read().count() > 0 {
+        play_sfx.send(PlaySfx(Sfx::Death));
+    }
+}
+
+fn duck_music(
+    time: Res<Time<Real>>,
+    settings: Res<AudioSettings>,
+    in_game_state: Res<State<InGameState>>,
+    mut duck: ResMut<MusicDuck>,
+    music_query: Query<&AudioSink, With<Music>>,
+) {
+    let target = match in_game_state.get() {
+        InGameState::Playing | InGameState::Victory => 1.0,
+        _ => MUSIC_DUCK_LEVEL,
+    };
+    let step = MUSIC_DUCK_SPEED * time.delta_seconds();
+    let level = duck.0 + (target - duck.0).clamp(-step, step);
+    if level == duck.0 && !settings.is_changed() {
+        return;
+    }
+    duck.0 = level;
+
+    for sink in music_query.iter() {
+        sink.set_volume(settings.volume(AudioBus::Music) * duck.0);
+    }
+}
+
+fn apply_bus_volumes(
+    settings: Res<AudioSettings>,
+    emitter_query: Query<(&AudioSink, &SoundEmitter), Without<Music>>,
+) {
+    for (sink, SoundEmitter(bus)) in emitter_query.iter() {
+        sink.set_volume(settings.volume(*bus));
+    }
+}
+
+fn save_audio_settings(settings: Res<AudioSettings>) {
+    storage::save(AUDIO_SETTINGS_KEY, &*settings);
+}
+
+fn toggle_mute_key(keyboard_input: Res<ButtonInput<KeyCode>>, mut settings: ResMut<AudioSettings>) {
+    if keyboard_input.just_pressed(KeyCode::KeyM) {
+        settings.muted = !settings.muted;
+    }
+}
+
+// Volume controls: a slider per bus plus a mute toggle, for menus to embed
+pub fn spawn_volume_controls(menu: &mut MenuBuilder, settings: &AudioSettings) {
+    let rows = [
+        ("audio.master", None),
+        ("audio.music", Some(AudioBus::Music)),
+        ("audio.effects", Some(AudioBus::Sfx)),
+        ("audio.interface", Some(AudioBus::Ui)),
+    ];
+
+    for (label, bus) in rows {
+        menu.slider(label, settings.level(bus), VOLUME_STEP, VolumeSlider(bus));
+    }
+    menu.toggle(settings.muted, "audio.sound_off", "audio.sound_on", MuteToggle);
+}
+
+fn volume_slider_input(
+    mut settings: ResMut<AudioSettings>,
+    mut changed: EventReader<SliderChanged>,
+    slider_query: Query<&VolumeSlider>,
+) {
+    for SliderChanged { entity, value } in changed.read() {
+        if let Ok(VolumeSlider(bus)) = slider_query.get(*entity) {
+            settings.set_level(*bus, *value);
+        }
+    }
+}
+
+fn mute_toggle_input(
+    mut settings: ResMut<AudioSettings>,
+    mut activated: EventReader<WidgetActivated>,
+    toggle_query: Query<&Toggle, With<MuteToggle>>,
+) {
+    for WidgetActivated(entity) in activated.read() {
+        if let Ok(toggle) = toggle_query.get(*entity) {
+            settings.muted = toggle.on;
+        }
+    }
+}
+
+// Keeps the controls in step with changes made elsewhere, e.g. the M key
+fn update_volume_controls(
+    settings: Res<AudioSettings>,
+    mut slider_query: Query<(&mut Slider, &VolumeSlider)>,
+    mut toggle_query: Query<&mut Toggle, With<MuteToggle>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    for (mut slider, VolumeSlider(bus)) in slider_query.iter_mut() {
+        let level = settings.level(*bus);
+        if slider.value != level {
+            slider.value = level;
+        }
+    }
+    for mut toggle in toggle_query.iter_mut() {
+        if toggle.on != settings.muted {
+            toggle.on = settings.muted;
+        }
+    }
+}
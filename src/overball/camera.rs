@@ -0,0 +1,219 @@
+use bevy::input::mouse::MouseWheel;
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use super::accessibility::AccessibilitySettings;
+use super::components::*;
+use super::constants::*;
+use super::events::{DoorBumped, PlayerDied};
+use super::simulation::interpolate_transforms;
+use super::states::{AppState, InGameState};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CameraMode {
+    // Straight down, following the ball
+    #[default]
+    Follow,
+    // Angled behind the ball, looking ahead in the direction it rolls
+    Chase,
+    // Fixed view of the whole level
+    TopDown,
+}
+
+impl CameraMode {
+    fn next(self) -> CameraMode {
+        match self {
+            CameraMode::Follow => CameraMode::Chase,
+            CameraMode::Chase => CameraMode::TopDown,
+            CameraMode::TopDown => CameraMode::Follow,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct CameraRig {
+    pub mode: CameraMode,
+    pub zoom: f32,
+    // 0 to 1, shake strength grows with its square and decays over time
+    pub trauma: f32,
+    // Smoothed, unshaken pose
+    position: Vec3,
+    target: Vec3,
+    heading: Vec3,
+}
+
+impl CameraRig {
+    pub fn new(position: Vec3, target: Vec3) -> Self {
+        CameraRig {
+            mode: CameraMode::default(),
+            zoom: 1.0,
+            trauma: 0.0,
+            position,
+            target,
+            heading: Vec3::X,
+        }
+    }
+}
+
+// Adds trauma to the camera rig, e.g. for impacts
+#[derive(Event)]
+pub struct CameraShake(pub f32);
+
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<CameraShake>().add_systems(
+            Update,
+            (
+                camera_input.run_if(in_state(InGameState::Playing)),
+                shake_on_impacts,
+                add_camera_trauma,
+                update_camera_rig.after(interpolate_transforms),
+            )
+                .chain()
+                .run_if(in_state(AppState::Game)),
+        );
+    }
+}
+
+fn camera_input(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut mouse_wheel_events: EventReader<MouseWheel>,
+    mut rig_query: Query<&mut CameraRig>,
+) {
+    let scroll: f32 = mouse_wheel_events
+        .read()
+        .map(|event| event.y.signum())
+        .sum();
+
+    for mut rig in rig_query.iter_mut() {
+        if keyboard_input.just_pressed(KeyCode::KeyC) {
+            rig.mode = rig.mode.next();
+        }
+
+        let mut zoom_steps = -scroll;
+        if keyboard_input.just_pressed(KeyCode::Equal) {
+            zoom_steps -= 1.0;
+        }
+        if keyboard_input.just_pressed(KeyCode::Minus) {
+            zoom_steps += 1.0;
+        }
+        rig.zoom =
+            (rig.zoom * CAMERA_ZOOM_STEP.powf(zoom_steps)).clamp(CAMERA_MIN_ZOOM, CAMERA_MAX_ZOOM);
+    }
+}
+
+fn shake_on_impacts(
+    mut door_bumped: EventReader<DoorBumped>,
+    mut player_died: EventReader<PlayerDied>,
+    mut camera_shake: EventWriter<CameraShake>,
+) {
+    if door_bumped.read().count() > 0 {
+        camera_shake.send(CameraShake(DOOR_THUNK_CAMERA_TRAUMA));
+    }
+    // Dying is reported every tick until the state changes, so it only shakes once
+    if player_died.read().count() > 0 {
+        camera_shake.send(CameraShake(DEATH_CAMERA_TRAUMA));
+    }
+}
+
+fn add_camera_trauma(
+    settings: Res<AccessibilitySettings>,
+    mut shake_events: EventReader<CameraShake>,
+    mut rig_query: Query<&mut CameraRig>,
+) {
+    if settings.reduced_motion {
+        shake_events.clear();
+        return;
+    }
+    for CameraShake(trauma) in shake_events.read() {
+        for mut rig in rig_query.iter_mut() {
+            rig.trauma = (rig.trauma + trauma).min(1.0);
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn update_camera_rig(
+    time: Res<Time>,
+    player_query: Query<(&Transform, &Velocity), (With<Player>, Without<CameraRig>)>,
+    tile_query: Query<&Tile>,
+    goal_query: Query<&Transform, (With<WinningTile>, Without<CameraRig>)>,
+    mut rig_query: Query<(&mut Transform, &mut CameraRig)>,
+) {
+    let delta = time.delta_seconds();
+    let smoothing = 1.0 - (-CAMERA_SMOOTHING * delta).exp();
+
+    // The middle of the tiles and the goal, wherever the level is laid out
+    let level_center = tile_query
+        .iter()
+        .map(|tile| tile.position.xz())
+        .chain(goal_query.iter().map(|transform| transform.translation.xz()))
+        .map(|point| Rect::from_center_size(point, Vec2::ZERO))
+        .reduce(|a, b| a.union(b))
+        .map_or(Vec2::ZERO, |bounds| bounds.center());
+    let level_center = Vec3::new(level_center.x, 0.0, level_center.y);
+
+    for (mut transform, mut rig) in rig_query.iter_mut() {
+        let (ball, velocity) = player_query
+            .get_single()
+            .map_or((Vec3::ZERO, Vec3::ZERO), |(transform, velocity)| {
+                (transform.translation, velocity.linvel)
+            });
+        // Don't chase a ball falling off the map
+        let focus = ball.with_y(ball.y.max(0.0));
+        let rolling = velocity.with_y(0.0);
+
+        if let Some(direction) = rolling.try_normalize().filter(|_| rolling.length() > 0.5) {
+            let turn = 1.0 - (-CAMERA_HEADING_SMOOTHING * delta).exp();
+            rig.heading = rig
+                .heading
+                .lerp(direction, turn)
+                .try_normalize()
+                .unwrap_or(direction);
+        }
+
+        let (desired_position, desired_target, up) = match rig.mode {
+            CameraMode::Follow => {
+                let center = focus.with_y(0.0) + rolling * CAMERA_LOOK_AHEAD_TIME;
+                (
+                    center + Vec3::Y * CAMERA_FOLLOW_HEIGHT * rig.zoom,
+                    center,
+                    Vec3::NEG_Z,
+                )
+            }
+            CameraMode::Chase => (
+                focus
+                    + (Vec3::Y * CAMERA_CHASE_HEIGHT - rig.heading * CAMERA_CHASE_DISTANCE)
+                        * rig.zoom,
+                focus + rig.heading * CAMERA_LOOK_AHEAD_DISTANCE,
+                Vec3::Y,
+            ),
+            CameraMode::TopDown => (
+                level_center + Vec3::Y * CAMERA_TOP_DOWN_HEIGHT * rig.zoom,
+                level_center,
+                Vec3::NEG_Z,
+            ),
+        };
+
+        rig.position = rig.position.lerp(desired_position, smoothing);
+        rig.target = rig.target.lerp(desired_target, smoothing);
+        rig.trauma = (rig.trauma - CAMERA_TRAUMA_DECAY * delta).max(0.0);
+
+        // Layered sines stand in for noise, different per axis so the shake doesn't look periodic
+        let shake = rig.trauma * rig.trauma;
+        let t = time.elapsed_seconds();
+        let offset = Vec3::new(
+            (t * 37.0).sin() + (t * 23.0).sin() * 0.5,
+            (t * 31.0).sin() * 0.5,
+            (t * 41.0).sin() + (t * 17.0).sin() * 0.5,
+        ) * shake
+            * CAMERA_SHAKE_OFFSET;
+        let roll = (t * 29.0).sin() * shake * CAMERA_SHAKE_ROLL;
+
+        *transform =
+            Transform::from_translation(rig.position + offset).looking_at(rig.target + offset, up);
+        transform.rotate_local_z(roll);
+    }
+}
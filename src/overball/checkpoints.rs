@@ -0,0 +1,181 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use super::accessibility::AccessibilitySettings;
+use super::components::*;
+use super::constants::*;
+use super::events::CheckpointReached;
+use super::resources::*;
+use super::states::{GameplaySet, InGameState};
+
+// Checkpoints move the respawn point, and respawning fades in with a moment of invulnerability
+pub struct CheckpointPlugin;
+
+#[derive(Component)]
+struct RespawnFade {
+    timer: Timer,
+}
+
+impl Plugin for CheckpointPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RespawnPoint>()
+            .add_systems(
+                OnEnter(InGameState::Reset),
+                (reset_respawn_point, despawn_respawn_fade),
+            )
+            .add_systems(
+                OnTransition {
+                    exited: InGameState::PlayerDied,
+                    entered: InGameState::Playing,
+                },
+                start_respawn,
+            )
+            .add_systems(
+                FixedUpdate,
+                (detect_checkpoints, tick_invulnerability).in_set(GameplaySet::Update),
+            )
+            .add_systems(
+                Update,
+                (blink_invulnerable_player, fade_in_after_respawn)
+                    .run_if(in_state(InGameState::Playing)),
+            );
+    }
+}
+
+pub fn reset_respawn_point(mut respawn_point: ResMut<RespawnPoint>) {
+    *respawn_point = RespawnPoint::default();
+}
+
+fn despawn_respawn_fade(mut commands: Commands, query: Query<Entity, With<RespawnFade>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+pub fn checkpoint_color(activated: bool) -> (Color, LinearRgba) {
+    if activated {
+        (Color::srgb(0.2, 1.0, 1.0), LinearRgba::rgb(0.0, 1.5, 1.5))
+    } else {
+        (Color::srgb(0.1, 0.35, 0.4), LinearRgba::BLACK)
+    }
+}
+
+// Lights up the checkpoint the respawn point belongs to and dims the rest
+pub fn set_active_checkpoint(
+    respawn_position: Vec3,
+    checkpoint_query: &mut Query<(&mut Checkpoint, &Handle<StandardMaterial>)>,
+    materials: &mut Assets<StandardMaterial>,
+) {
+    for (mut checkpoint, material_handle) in checkpoint_query.iter_mut() {
+        checkpoint.activated = checkpoint.respawn_position == respawn_position;
+        if let Some(material) = materials.get_mut(material_handle) {
+            let (base_color, emissive) = checkpoint_color(checkpoint.activated);
+            material.base_color = base_color;
+            material.emissive = emissive;
+        }
+    }
+}
+
+fn detect_checkpoints(
+    mut collision_events: EventReader<CollisionEvent>,
+    mut respawn_point: ResMut<RespawnPoint>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    player_query: Query<(), With<Player>>,
+    mut checkpoint_query: Query<(&mut Checkpoint, &Handle<StandardMaterial>)>,
+    mut checkpoint_reached: EventWriter<CheckpointReached>,
+) {
+    for collision_event in collision_events.read() {
+        let CollisionEvent::Started(entity1, entity2, _) = collision_event else {
+            continue;
+        };
+        let checkpoint_entity = if player_query.contains(*entity1) {
+            *entity2
+        } else if player_query.contains(*entity2) {
+            *entity1
+        } else {
+            continue;
+        };
+        let Ok((checkpoint, _)) = checkpoint_query.get(checkpoint_entity) else {
+            continue;
+        };
+        if checkpoint.activated {
+            continue;
+        }
+
+        respawn_point.0 = checkpoint.respawn_position;
+        set_active_checkpoint(respawn_point.0, &mut checkpoint_query, &mut materials);
+        checkpoint_reached.send(CheckpointReached {
+            position: respawn_point.0,
+        });
+    }
+}
+
+fn start_respawn(mut commands: Commands, player_query: Query<Entity, With<Player>>) {
+    for entity in player_query.iter() {
+        commands.entity(entity).insert(Invulnerable {
+            timer: Timer::from_seconds(INVULNERABILITY_TIME, TimerMode::Once),
+        });
+    }
+
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                ..default()
+            },
+            background_color: BackgroundColor(Color::BLACK),
+            z_index: ZIndex::Global(10),
+            ..default()
+        },
+        RespawnFade {
+            timer: Timer::from_seconds(RESPAWN_FADE_TIME, TimerMode::Once),
+        },
+    ));
+}
+
+fn tick_invulnerability(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Invulnerable)>,
+) {
+    for (entity, mut invulnerable) in query.iter_mut() {
+        if invulnerable.timer.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<Invulnerable>();
+        }
+    }
+}
+
+fn blink_invulnerable_player(
+    settings: Res<AccessibilitySettings>,
+    mut query: Query<(&mut Visibility, Option<&Invulnerable>), With<Player>>,
+) {
+    for (mut visibility, invulnerable) in query.iter_mut() {
+        // Flicker about eight times a second while protected, unless flashing is turned off
+        let hidden = !settings.reduced_motion
+            && invulnerable.is_some_and(|invulnerable| {
+                (invulnerable.timer.elapsed_secs() * 16.0) as u32 % 2 == 1
+            });
+        visibility.set_if_neq(if hidden {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        });
+    }
+}
+
+fn fade_in_after_respawn(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut BackgroundColor, &mut RespawnFade)>,
+) {
+    for (entity, mut background_color, mut fade) in query.iter_mut() {
+        fade.timer.tick(time.delta());
+        if fade.timer.finished() {
+            commands.entity(entity).despawn_recursive();
+        } else {
+            background_color.0 = Color::BLACK.with_alpha(fade.timer.fraction_remaining());
+        }
+    }
+}
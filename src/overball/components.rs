@@ -1,6 +1,7 @@
 use bevy::prelude::*;
-use bevy_rapier3d::dynamics::RigidBody;
-use bevy_rapier3d::geometry::{Collider, Restitution};
+use bevy_rapier3d::dynamics::{Damping, ExternalForce, ExternalImpulse, RigidBody, Velocity};
+use bevy_rapier3d::geometry::{Collider, ColliderMassProperties, Friction, Restitution};
+use serde::{Deserialize, Serialize};
 
 #[derive(Component)]
 pub struct GameMap;
@@ -9,6 +10,73 @@ pub struct GameMap;
 pub struct Tile {
     pub position: Vec3,
     pub activated: bool,
+    pub kind: TileKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TileKind {
+    Normal,
+    // Pushes the ball along `direction` when it rolls on
+    Boost { direction: Vec3 },
+    // Lowers the ball's damping and friction while it is on the tile
+    Ice,
+    // Falls away a short time after the ball first touches it
+    Crumbling,
+    // Moves the ball to the other teleporter on the same channel
+    Teleporter { channel: u32 },
+    // Kills the ball on contact
+    Hazard,
+    // Toggles the switch doors on the same channel
+    Switch { channel: u32 },
+}
+
+impl TileKind {
+    pub fn score(&self) -> u32 {
+        match self {
+            TileKind::Normal => 1,
+            TileKind::Boost { .. } => 1,
+            TileKind::Ice => 2,
+            TileKind::Crumbling => 3,
+            TileKind::Teleporter { .. } => 2,
+            TileKind::Hazard => 0,
+            TileKind::Switch { .. } => 1,
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            TileKind::Normal => Color::srgb(0.5, 0.5, 0.5),
+            TileKind::Boost { .. } => Color::srgb(1.0, 0.5, 0.0),
+            TileKind::Ice => Color::srgb(0.6, 0.85, 1.0),
+            TileKind::Crumbling => Color::srgb(0.45, 0.3, 0.15),
+            TileKind::Teleporter { .. } => Color::srgb(0.6, 0.2, 0.8),
+            TileKind::Hazard => Color::srgb(0.8, 0.1, 0.1),
+            TileKind::Switch { .. } => Color::srgb(0.8, 0.2, 0.6),
+        }
+    }
+
+    pub fn activated_color(&self) -> Color {
+        match self {
+            TileKind::Normal => Color::srgb(0.0, 1.0, 0.0),
+            TileKind::Boost { .. } => Color::srgb(1.0, 0.8, 0.3),
+            TileKind::Ice => Color::srgb(0.85, 1.0, 1.0),
+            TileKind::Crumbling => Color::srgb(0.65, 0.5, 0.3),
+            TileKind::Teleporter { .. } => Color::srgb(0.85, 0.55, 1.0),
+            TileKind::Hazard => Color::srgb(0.8, 0.1, 0.1),
+            TileKind::Switch { .. } => Color::srgb(1.0, 0.5, 0.85),
+        }
+    }
+
+    // Hazards never count towards the score
+    pub fn activates(&self) -> bool {
+        !matches!(self, TileKind::Hazard)
+    }
+}
+
+#[derive(Component)]
+pub struct CrumblingTile {
+    pub timer: Timer,
+    pub collapsed: bool,
 }
 
 #[derive(Component)]
@@ -23,14 +91,79 @@ pub struct WinningTileTimer(pub Timer);
 
 #[derive(Component)]
 pub struct Door {
-    pub required_score: u32,
-    pub is_open: bool,
+    pub requirement: DoorRequirement,
+    pub state: DoorState,
+    pub closed_position: Vec3,
+    // Closes again this long after opening, if set
+    pub close_timer: Option<Timer>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DoorRequirement {
+    Score(u32),
+    // Every tile between the two grid cells (inclusive) must be activated
+    Region { min: IVec2, max: IVec2 },
+    Key(u32),
+    // Toggled by the switch tiles on the same channel instead of by bumping
+    Switch(u32),
+}
+
+impl DoorRequirement {
+    pub fn color(&self) -> Color {
+        match self {
+            DoorRequirement::Score(_) => Color::srgb(0.0, 0.0, 1.0),
+            DoorRequirement::Region { .. } => Color::srgb(0.0, 0.6, 0.6),
+            DoorRequirement::Key(_) => Color::srgb(0.9, 0.75, 0.1),
+            DoorRequirement::Switch(_) => Color::srgb(0.8, 0.2, 0.6),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DoorState {
+    Closed,
+    Opening,
+    Open,
+    Closing,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HazardMotion {
+    // Patrols the waypoints in order, looping back to the first
+    Path { waypoints: &'static [Vec3], speed: f32 },
+    // Rotates in place around the y axis, in radians per second
+    Spin { speed: f32 },
+    // Swings back and forth along `axis` around its starting position
+    Sweep { axis: Vec3, amplitude: f32, period: f32 },
+}
+
+#[derive(Component)]
+pub struct MovingHazard {
+    pub motion: HazardMotion,
+    pub origin: Vec3,
+    pub elapsed: f32,
+    pub next_waypoint: usize,
+}
+
+#[derive(Component)]
+pub struct DoorKey {
+    pub id: u32,
+}
+
+#[derive(Component)]
+pub struct Checkpoint {
+    pub respawn_position: Vec3,
+    pub activated: bool,
+}
+
+// Hazards can't kill the ball for a moment after it respawns
+#[derive(Component)]
+pub struct Invulnerable {
+    pub timer: Timer,
 }
 
 #[derive(Component)]
 pub struct Ball {
-    pub position: Vec3,
-    pub velocity: Vec3,
     pub radius: f32,
 }
 
@@ -61,5 +194,11 @@ pub struct PlayerBundle {
     pub scene_bundle: SceneBundle,
     pub collider: Collider,
     pub restitution: Restitution,
+    pub friction: Friction,
+    pub mass_properties: ColliderMassProperties,
     pub rigid_body: RigidBody,
+    pub velocity: Velocity,
+    pub external_force: ExternalForce,
+    pub external_impulse: ExternalImpulse,
+    pub damping: Damping,
 }
@@ -9,14 +9,108 @@ pub const MIN_Z: f32 = -20.0;
 pub const MAX_Z: f32 = 20.0;
 
 pub const PLAYER_LIVES: u32 = 3;
+pub const HIGH_SCORE_SLOTS: usize = 10;
+pub const MAX_NAME_LENGTH: usize = 12;
 
-pub const MOVEMENT_SPEED: f32 = 2.0;
-pub const DAMPING_FACTOR: f32 = 0.7;
+// Physics and gameplay input run at this fixed rate
+pub const PHYSICS_TICK_RATE: f64 = 60.0;
+
+// Ball physics, applied through rapier
+pub const BALL_MASS: f32 = 1.0;
+pub const BALL_FRICTION: f32 = 0.8;
+pub const MIN_SENSITIVITY: f32 = 0.5;
+pub const MAX_SENSITIVITY: f32 = 2.0;
+pub const SENSITIVITY_STEP: f32 = 0.1;
+pub const MOVEMENT_FORCE: f32 = 2.0;
+pub const ROLLING_TORQUE: f32 = 0.4;
+pub const BALL_LINEAR_DAMPING: f32 = 0.35;
+pub const BALL_ANGULAR_DAMPING: f32 = 0.5;
+
+// Special tiles
+pub const BOOST_IMPULSE: f32 = 1.5;
+pub const ICE_FRICTION: f32 = 0.05;
+pub const ICE_LINEAR_DAMPING: f32 = 0.02;
+pub const ICE_ANGULAR_DAMPING: f32 = 0.02;
+pub const CRUMBLE_DELAY: f32 = 1.0;
+pub const CRUMBLE_FALL_SPEED: f32 = 2.0;
 
 pub const DOOR_POSITION: Vec3 = Vec3::new(10.5, 0.5, 0.0);
-pub const DOOR_REQUIRED_SCORE: u32 = 10;
+// Ten tiles' worth, fewer while a combo is going
+pub const DOOR_REQUIRED_SCORE: u32 = 10 * TILE_POINTS;
+pub const DOOR_SPEED: f32 = 0.5;
+// How far a door sinks into the floor when open
+pub const DOOR_TRAVEL: f32 = 1.0;
+pub const KEY_PICKUP_RADIUS: f32 = 0.75;
+pub const DOOR_THUNK_CAMERA_TRAUMA: f32 = 0.35;
+
+// Checkpoints
+pub const CHECKPOINT_RADIUS: f32 = 0.6;
+// Respawned balls drop in from this height above the checkpoint
+pub const RESPAWN_HEIGHT: f32 = 1.0;
+pub const INVULNERABILITY_TIME: f32 = 2.0;
+pub const RESPAWN_FADE_TIME: f32 = 0.75;
+
+// Camera
+pub const CAMERA_FOLLOW_HEIGHT: f32 = 18.0;
+pub const CAMERA_CHASE_HEIGHT: f32 = 5.0;
+pub const CAMERA_CHASE_DISTANCE: f32 = 7.0;
+pub const CAMERA_TOP_DOWN_HEIGHT: f32 = 40.0;
+// Seconds of velocity the follow camera leads the ball by
+pub const CAMERA_LOOK_AHEAD_TIME: f32 = 0.4;
+pub const CAMERA_LOOK_AHEAD_DISTANCE: f32 = 3.0;
+pub const CAMERA_SMOOTHING: f32 = 4.0;
+pub const CAMERA_HEADING_SMOOTHING: f32 = 2.0;
+pub const CAMERA_ZOOM_STEP: f32 = 1.1;
+pub const CAMERA_MIN_ZOOM: f32 = 0.5;
+pub const CAMERA_MAX_ZOOM: f32 = 2.5;
+pub const CAMERA_SHAKE_OFFSET: f32 = 0.6;
+pub const CAMERA_SHAKE_ROLL: f32 = 0.05;
+// Trauma lost per second
+pub const CAMERA_TRAUMA_DECAY: f32 = 1.2;
+
+// Death sequence
+pub const DEATH_EFFECT_TIME: f32 = 1.2;
+pub const RESPAWN_COUNTDOWN: u32 = 3;
+pub const DEATH_PARTICLE_COUNT: usize = 16;
+pub const DEATH_CAMERA_TRAUMA: f32 = 0.7;
 
 // UI Style
 pub const NORMAL_BUTTON: Color = Color::srgb(0.15, 0.15, 0.15);
 pub const HOVERED_BUTTON: Color = Color::srgb(0.25, 0.25, 0.25);
 pub const PRESSED_BUTTON: Color = Color::srgb(0.35, 0.75, 0.35);
+
+// Title menu
+pub const MENU_TRANSITION_TIME: f32 = 0.25;
+// How far a panel slides in from
+pub const MENU_SLIDE_DISTANCE: f32 = 80.0;
+// Radians per second
+pub const MENU_BALL_SPIN_SPEED: f32 = 0.6;
+
+// Scoring
+// Points for a tile worth 1, before the combo multiplier
+pub const TILE_POINTS: u32 = 10;
+// Seconds the next tile has to be activated in to keep the combo going
+pub const COMBO_WINDOW: f32 = 1.5;
+// Tiles in a combo for each step up of the multiplier
+pub const COMBO_TILES_PER_STEP: u32 = 5;
+pub const COMBO_MAX_MULTIPLIER: u32 = 4;
+pub const ROW_BONUS: u32 = 50;
+pub const REGION_BONUS: u32 = 100;
+
+// Victory screen
+pub const MAX_STARS: u32 = 3;
+// How long the run statistics take to count up
+pub const VICTORY_COUNT_UP_TIME: f32 = 1.2;
+// Gap between each star lighting up once the count is done
+pub const VICTORY_STAR_INTERVAL: f32 = 0.35;
+
+// HUD
+pub const HUD_ICON_SIZE: f32 = 28.0;
+pub const HUD_BAR_WIDTH: f32 = 180.0;
+// How far from the ball, on screen, the objective arrow sits
+pub const OBJECTIVE_ARROW_DISTANCE: f32 = 70.0;
+// The arrow hides once the ball is this close to the goal
+pub const OBJECTIVE_HIDE_DISTANCE: f32 = 3.0;
+pub const MINIMAP_WIDTH: f32 = 220.0;
+// Tiles are drawn a little smaller than their spacing, so the grid reads
+pub const MINIMAP_TILE_SIZE: f32 = 0.8;
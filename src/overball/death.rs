@@ -0,0 +1,236 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use rand::Rng;
+
+use super::components::*;
+use super::constants::*;
+use super::localization::LocalizedText;
+use super::resources::*;
+use super::states::InGameState;
+
+// Dying bursts or drops the ball, shows the lives left and counts down to the respawn,
+// or moves on to game over once the lives are gone
+pub struct DeathPlugin;
+
+#[derive(Resource)]
+struct DeathSequence {
+    timer: Timer,
+    counting_down: bool,
+    game_over: bool,
+}
+
+#[derive(Component)]
+struct DeathUI;
+
+#[derive(Component)]
+struct CountdownText;
+
+#[derive(Component)]
+struct DeathParticle {
+    velocity: Vec3,
+    timer: Timer,
+}
+
+impl Plugin for DeathPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(InGameState::PlayerDied), start_death_sequence)
+            .add_systems(
+                Update,
+                (
+                    drop_fallen_ball.before(update_death_sequence),
+                    update_death_sequence,
+                    update_death_particles,
+                )
+                    .run_if(in_state(InGameState::PlayerDied)),
+            )
+            .add_systems(OnExit(InGameState::PlayerDied), despawn_death_ui);
+    }
+}
+
+fn start_death_sequence(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut context: ResMut<GameContext>,
+    mut rng: ResMut<GameRng>,
+    mut player_query: Query<
+        (Entity, &Transform, &mut Velocity, &mut ExternalForce, &mut Visibility),
+        With<Player>,
+    >,
+) {
+    context.deaths += 1;
+    let game_over = context.lives == 0;
+    if !game_over {
+        context.lives -= 1;
+    }
+
+    for (entity, transform, mut velocity, mut external_force, mut visibility) in
+        player_query.iter_mut()
+    {
+        *external_force = ExternalForce::default();
+
+        // A ball that fell off the map keeps falling out of view, anything else bursts
+        if transform.translation.y < 0.0 {
+            continue;
+        }
+        *velocity = Velocity::zero();
+        *visibility = Visibility::Hidden;
+        commands.entity(entity).insert(RigidBodyDisabled);
+
+        let mesh = meshes.add(Sphere::new(0.08));
+        let material = materials.add(StandardMaterial {
+            base_color: Color::srgb(1.0, 0.5, 0.1),
+            emissive: LinearRgba::rgb(3.0, 1.0, 0.0),
+            ..default()
+        });
+        // Seeded with the run, so a replay bursts the same way
+        for index in 0..DEATH_PARTICLE_COUNT {
+            let angle =
+                (index as f32 + rng.0.gen_range(-0.5..0.5)) / DEATH_PARTICLE_COUNT as f32 * TAU;
+            let direction = Vec3::new(angle.cos(), rng.0.gen_range(1.0..2.0), angle.sin());
+            commands.spawn((
+                PbrBundle {
+                    mesh: mesh.clone(),
+                    material: material.clone(),
+                    transform: Transform::from_translation(transform.translation),
+                    ..default()
+                },
+                DeathParticle {
+                    velocity: direction * 3.0,
+                    timer: Timer::from_seconds(DEATH_EFFECT_TIME, TimerMode::Once),
+                },
+                GameMap,
+            ));
+        }
+    }
+
+    let font = asset_server.load("fonts/montserrat.ttf");
+    let text_style = TextStyle {
+        font: font.clone(),
+        font_size: 50.0,
+        color: Color::WHITE,
+    };
+    let lives_message = if game_over {
+        LocalizedText::new("death.no_lives")
+    } else {
+        LocalizedText::new("death.lives_remaining").with_count(context.lives as i64)
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(20.0),
+                    ..default()
+                },
+                ..default()
+            },
+            DeathUI,
+        ))
+        .with_children(|parent| {
+            parent.spawn((TextBundle::from_section("", text_style.clone()), lives_message));
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 100.0,
+                        ..text_style
+                    },
+                ),
+                CountdownText,
+            ));
+        });
+
+    commands.insert_resource(DeathSequence {
+        timer: Timer::from_seconds(DEATH_EFFECT_TIME, TimerMode::Once),
+        counting_down: false,
+        game_over,
+    });
+}
+
+fn update_death_sequence(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut sequence: ResMut<DeathSequence>,
+    respawn_point: Res<RespawnPoint>,
+    mut countdown_query: Query<&mut Text, With<CountdownText>>,
+    mut player_query: Query<
+        (Entity, &mut Transform, &mut Velocity, &mut Visibility),
+        With<Player>,
+    >,
+    mut next_state: ResMut<NextState<InGameState>>,
+) {
+    if !sequence.timer.tick(time.delta()).finished() {
+        if sequence.counting_down {
+            let remaining = sequence.timer.remaining_secs().ceil() as u32;
+            for mut text in countdown_query.iter_mut() {
+                text.sections[0].value = remaining.to_string();
+            }
+        }
+        return;
+    }
+
+    if sequence.game_over {
+        next_state.set(InGameState::GameOver);
+    } else if !sequence.counting_down {
+        sequence.counting_down = true;
+        sequence.timer = Timer::from_seconds(RESPAWN_COUNTDOWN as f32, TimerMode::Once);
+    } else {
+        for (entity, mut transform, mut velocity, mut visibility) in player_query.iter_mut() {
+            transform.translation = respawn_point.0;
+            transform.rotation = Quat::IDENTITY;
+            *velocity = Velocity::zero();
+            *visibility = Visibility::Inherited;
+            commands.entity(entity).remove::<RigidBodyDisabled>();
+        }
+        next_state.set(InGameState::Playing);
+    }
+}
+
+// Physics is held until the respawn, so a ball that fell off the map is dropped out of view
+// here instead
+#[allow(clippy::type_complexity)]
+fn drop_fallen_ball(
+    time: Res<Time>,
+    mut query: Query<(&mut Transform, &mut Velocity), (With<Player>, Without<RigidBodyDisabled>)>,
+) {
+    for (mut transform, mut velocity) in query.iter_mut() {
+        velocity.linvel.y -= 9.81 * time.delta_seconds();
+        transform.translation += velocity.linvel * time.delta_seconds();
+    }
+}
+
+fn update_death_particles(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Transform, &mut DeathParticle)>,
+) {
+    for (entity, mut transform, mut particle) in query.iter_mut() {
+        if particle.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        particle.velocity.y -= 9.81 * time.delta_seconds();
+        transform.translation += particle.velocity * time.delta_seconds();
+        transform.scale = Vec3::splat(particle.timer.fraction_remaining());
+    }
+}
+
+fn despawn_death_ui(
+    mut commands: Commands,
+    ui_query: Query<Entity, With<DeathUI>>,
+    particle_query: Query<Entity, With<DeathParticle>>,
+) {
+    for entity in ui_query.iter().chain(particle_query.iter()) {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.remove_resource::<DeathSequence>();
+}
@@ -0,0 +1,238 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use super::components::*;
+use super::constants::*;
+use super::events::{DoorBlocked, DoorBumped, DoorClosed, DoorOpened, KeyCollected};
+use super::localization::LocalizedText;
+use super::resources::*;
+use super::states::GameplaySet;
+use super::systems::detect_ball_on_tile;
+
+// Doors block the way to the winning tile until their requirement is met
+pub struct DoorPlugin;
+
+impl Plugin for DoorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            (
+                handle_door_collisions,
+                apply_switch_tiles.after(detect_ball_on_tile),
+                collect_keys,
+                spin_keys,
+                close_timed_doors,
+                animate_doors,
+            )
+                .in_set(GameplaySet::Update),
+        );
+    }
+}
+
+pub fn set_door_state(commands: &mut Commands, entity: Entity, door: &mut Door, state: DoorState) {
+    door.state = state;
+    match state {
+        // The door only stops blocking once it is fully sunk into the floor
+        DoorState::Open => {
+            commands.entity(entity).insert(ColliderDisabled);
+        }
+        DoorState::Closing => {
+            commands.entity(entity).remove::<ColliderDisabled>();
+        }
+        DoorState::Closed | DoorState::Opening => {}
+    }
+}
+
+pub fn requirement_met(
+    requirement: &DoorRequirement,
+    context: &GameContext,
+    tile_query: &Query<&Tile>,
+) -> bool {
+    match requirement {
+        DoorRequirement::Score(required_score) => context.score >= *required_score,
+        DoorRequirement::Region { min, max } => tile_query
+            .iter()
+            .filter(|tile| {
+                let cell = TileGrid::cell(tile.position);
+                cell.cmpge(*min).all() && cell.cmple(*max).all() && tile.kind.activates()
+            })
+            .all(|tile| tile.activated),
+        DoorRequirement::Key(id) => context.keys.contains(id),
+        DoorRequirement::Switch(_) => false,
+    }
+}
+
+pub fn requirement_message(requirement: &DoorRequirement) -> LocalizedText {
+    match requirement {
+        DoorRequirement::Score(required_score) => {
+            LocalizedText::new("door.needs_score").with_arg("score", required_score)
+        }
+        DoorRequirement::Region { .. } => LocalizedText::new("door.needs_region"),
+        DoorRequirement::Key(_) => LocalizedText::new("door.needs_key"),
+        DoorRequirement::Switch(_) => LocalizedText::new("door.needs_switch"),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn handle_door_collisions(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionEvent>,
+    context: Res<GameContext>,
+    ball_query: Query<&Transform, With<Player>>,
+    mut door_query: Query<(Entity, &mut Door)>,
+    tile_query: Query<&Tile>,
+    mut door_bumped: EventWriter<DoorBumped>,
+    mut door_opened: EventWriter<DoorOpened>,
+    mut door_blocked: EventWriter<DoorBlocked>,
+) {
+    for collision_event in collision_events.read() {
+        if let CollisionEvent::Started(entity1, entity2, _) = collision_event {
+            // Rapier doesn't guarantee the order of the pair
+            let (ball_entity, door_entity) = if ball_query.contains(*entity1) {
+                (*entity1, *entity2)
+            } else {
+                (*entity2, *entity1)
+            };
+
+            if let (Ok(ball_transform), Ok((door_entity, mut door))) =
+                (ball_query.get(ball_entity), door_query.get_mut(door_entity))
+            {
+                if door.state != DoorState::Closed {
+                    continue;
+                }
+
+                debug!(
+                    "Ball collided with door at position: {:?}",
+                    ball_transform.translation
+                );
+                door_bumped.send(DoorBumped { door: door_entity });
+
+                if requirement_met(&door.requirement, &context, &tile_query) {
+                    set_door_state(&mut commands, door_entity, &mut door, DoorState::Opening);
+                    door_opened.send(DoorOpened { door: door_entity });
+                } else {
+                    door_blocked.send(DoorBlocked {
+                        door: door_entity,
+                        requirement: door.requirement,
+                    });
+                }
+            }
+        }
+    }
+}
+
+// Switch tiles toggle their doors every time the ball rolls onto them
+fn apply_switch_tiles(
+    mut commands: Commands,
+    current_tile: Res<CurrentTile>,
+    tile_query: Query<&Tile>,
+    mut door_query: Query<(Entity, &mut Door)>,
+    mut door_opened: EventWriter<DoorOpened>,
+    mut door_closed: EventWriter<DoorClosed>,
+) {
+    if !current_tile.is_changed() {
+        return;
+    }
+
+    let Some(TileKind::Switch { channel }) = current_tile
+        .0
+        .and_then(|entity| tile_query.get(entity).ok())
+        .map(|tile| tile.kind)
+    else {
+        return;
+    };
+
+    for (entity, mut door) in door_query.iter_mut() {
+        if door.requirement != DoorRequirement::Switch(channel) {
+            continue;
+        }
+
+        let next_state = match door.state {
+            DoorState::Closed | DoorState::Closing => DoorState::Opening,
+            DoorState::Open | DoorState::Opening => DoorState::Closing,
+        };
+        set_door_state(&mut commands, entity, &mut door, next_state);
+        if next_state == DoorState::Opening {
+            door_opened.send(DoorOpened { door: entity });
+        } else {
+            door_closed.send(DoorClosed { door: entity });
+        }
+    }
+}
+
+fn collect_keys(
+    mut commands: Commands,
+    mut context: ResMut<GameContext>,
+    ball_query: Query<&Transform, With<Player>>,
+    key_query: Query<(Entity, &Transform, &DoorKey)>,
+    mut key_collected: EventWriter<KeyCollected>,
+) {
+    if let Ok(ball_transform) = ball_query.get_single() {
+        for (entity, key_transform, key) in key_query.iter() {
+            if ball_transform.translation.distance(key_transform.translation) < KEY_PICKUP_RADIUS {
+                context.keys.push(key.id);
+                commands.entity(entity).despawn_recursive();
+                key_collected.send(KeyCollected { id: key.id });
+            }
+        }
+    }
+}
+
+fn spin_keys(time: Res<Time>, mut query: Query<&mut Transform, With<DoorKey>>) {
+    for mut transform in query.iter_mut() {
+        transform.rotate_y(2.0 * time.delta_seconds());
+    }
+}
+
+fn close_timed_doors(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Door)>,
+    mut door_closed: EventWriter<DoorClosed>,
+) {
+    for (entity, mut door) in query.iter_mut() {
+        if door.state != DoorState::Open {
+            continue;
+        }
+
+        let finished = door
+            .close_timer
+            .as_mut()
+            .is_some_and(|timer| timer.tick(time.delta()).finished());
+        if finished {
+            set_door_state(&mut commands, entity, &mut door, DoorState::Closing);
+            door_closed.send(DoorClosed { door: entity });
+        }
+    }
+}
+
+fn animate_doors(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Transform, &mut Door)>,
+) {
+    for (entity, mut transform, mut door) in query.iter_mut() {
+        let open_height = door.closed_position.y - DOOR_TRAVEL;
+        let step = DOOR_SPEED * time.delta_seconds();
+
+        match door.state {
+            DoorState::Opening => {
+                transform.translation.y = (transform.translation.y - step).max(open_height);
+                if transform.translation.y <= open_height {
+                    if let Some(timer) = door.close_timer.as_mut() {
+                        timer.reset();
+                    }
+                    set_door_state(&mut commands, entity, &mut door, DoorState::Open);
+                }
+            }
+            DoorState::Closing => {
+                let closed_height = door.closed_position.y;
+                transform.translation.y = (transform.translation.y + step).min(closed_height);
+                if transform.translation.y >= closed_height {
+                    set_door_state(&mut commands, entity, &mut door, DoorState::Closed);
+                }
+            }
+            DoorState::Closed | DoorState::Open => {}
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use bevy::prelude::*;
+
+use super::components::*;
+use super::states::{GameplaySet, InGameState};
+
+// Typed events for what happens in a run. The rules send them, and audio, UI, effects and
+// stats listen for them rather than being called from the rules.
+pub struct GameplayEventsPlugin;
+
+// The ball rolled onto a tile for the first time
+#[derive(Event, Debug, Clone, Copy)]
+pub struct TileActivated {
+    pub tile: Entity,
+    pub kind: TileKind,
+}
+
+#[derive(Event, Debug, Clone, Copy)]
+pub struct ScoreChanged {
+    pub score: u32,
+    pub delta: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bonus {
+    // Every tile in a row of the grid
+    Row,
+    // Every tile in a door's region
+    Region,
+}
+
+#[derive(Event, Debug, Clone, Copy)]
+pub struct BonusAwarded {
+    pub bonus: Bonus,
+    pub points: u32,
+}
+
+// The ball ran into a closed door, whether or not that opens it
+#[derive(Event, Debug, Clone, Copy)]
+pub struct DoorBumped {
+    pub door: Entity,
+}
+
+#[derive(Event, Debug, Clone, Copy)]
+pub struct DoorBlocked {
+    pub door: Entity,
+    pub requirement: DoorRequirement,
+}
+
+// A door started opening
+#[derive(Event, Debug, Clone, Copy)]
+pub struct DoorOpened {
+    pub door: Entity,
+}
+
+// A door started closing again
+#[derive(Event, Debug, Clone, Copy)]
+pub struct DoorClosed {
+    pub door: Entity,
+}
+
+#[derive(Event, Debug, Clone, Copy)]
+pub struct KeyCollected {
+    pub id: u32,
+}
+
+#[derive(Event, Debug, Clone, Copy)]
+pub struct CheckpointReached {
+    pub position: Vec3,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeathCause {
+    OutOfBounds,
+    Hazard,
+    // A hazard tile or one that crumbled away
+    Tile,
+}
+
+// Sent every tick the ball is dying until the state changes, so listeners that should only
+// react once handle a frame's worth together
+#[derive(Event, Debug, Clone, Copy)]
+pub struct PlayerDied {
+    pub cause: DeathCause,
+}
+
+#[derive(Event, Debug, Clone, Copy)]
+pub struct LevelCompleted;
+
+impl Plugin for GameplayEventsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<TileActivated>()
+            .add_event::<ScoreChanged>()
+            .add_event::<BonusAwarded>()
+            .add_event::<DoorBumped>()
+            .add_event::<DoorBlocked>()
+            .add_event::<DoorOpened>()
+            .add_event::<DoorClosed>()
+            .add_event::<KeyCollected>()
+            .add_event::<CheckpointReached>()
+            .add_event::<PlayerDied>()
+            .add_event::<LevelCompleted>()
+            .add_systems(
+                FixedUpdate,
+                end_play_on_outcome
+                    .after(GameplaySet::Update)
+                    .run_if(in_state(InGameState::Playing)),
+            );
+    }
+}
+
+// Dying wins over finishing if both happen on the same tick
+fn end_play_on_outcome(
+    mut died: EventReader<PlayerDied>,
+    mut completed: EventReader<LevelCompleted>,
+    mut next_state: ResMut<NextState<InGameState>>,
+) {
+    if died.read().count() > 0 {
+        completed.clear();
+        next_state.set(InGameState::PlayerDied);
+    } else if completed.read().count() > 0 {
+        next_state.set(InGameState::Victory);
+    }
+}
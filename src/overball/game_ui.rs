@@ -1,16 +1,112 @@
-use super::resources::GameContext;
-use super::states::GameplaySet;
-use super::states::InGameState;
+use super::components::*;
+use super::constants::*;
+use super::doors::{requirement_met, requirement_message};
+use super::events::{
+    Bonus, BonusAwarded, CheckpointReached, DoorBlocked, KeyCollected, ScoreChanged,
+};
+use super::localization::LocalizedText;
+use super::resources::{Combo, GameContext, RunTimer};
+use super::run_timer::format_time;
+use super::states::{AppState, GameplaySet, InGameState};
 use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
 use bevy::time::Timer;
 
 // UI components
 #[derive(Component)]
-struct LivesText;
+struct GameUI;
+
+// One per life the player starts with, filled while that life is left
+#[derive(Component)]
+struct Heart(u32);
 
 #[derive(Component)]
 struct ScoreText;
 
+#[derive(Component)]
+struct TimerText;
+
+// Shown while a score door is still closed
+#[derive(Component)]
+struct DoorProgress;
+
+#[derive(Component)]
+struct DoorProgressText;
+
+#[derive(Component)]
+struct DoorProgressFill;
+
+// Shown while a combo is going, the bar runs down until it breaks
+#[derive(Component)]
+struct ComboMeter;
+
+#[derive(Component)]
+struct ComboText;
+
+#[derive(Component)]
+struct ComboFill;
+
+// Points from the ball to the winning tile once every door is open
+#[derive(Component)]
+struct ObjectiveArrow;
+
+// White icons drawn at startup, tinted per use
+#[derive(Resource)]
+struct HudAssets {
+    heart: Handle<Image>,
+    arrow: Handle<Image>,
+}
+
+impl FromWorld for HudAssets {
+    fn from_world(world: &mut World) -> Self {
+        let mut images = world.resource_mut::<Assets<Image>>();
+        HudAssets {
+            // The heart curve (x² + y² - 1)³ - x²y³ = 0, nudged down to sit centered
+            heart: images.add(mask_image(|point| {
+                let point = point * 1.3 + Vec2::Y * 0.15;
+                (point.length_squared() - 1.0).powi(3) - point.x.powi(2) * point.y.powi(3) <= 0.0
+            })),
+            // Triangle pointing up
+            arrow: images.add(mask_image(|point| {
+                point.y >= -0.7 && point.x.abs() <= 0.7 * (0.9 - point.y) / 1.6
+            })),
+        }
+    }
+}
+
+// Square white image, opaque where `inside` holds for the point in -1 to 1 with y up
+fn mask_image(inside: impl Fn(Vec2) -> bool) -> Image {
+    const SIZE: u32 = 64;
+    let mut data = Vec::with_capacity((SIZE * SIZE * 4) as usize);
+    for row in 0..SIZE {
+        for column in 0..SIZE {
+            let point = Vec2::new(column as f32 + 0.5, row as f32 + 0.5) / SIZE as f32 * 2.0 - 1.0;
+            let alpha = if inside(point * Vec2::new(1.0, -1.0)) { 255 } else { 0 };
+            data.extend_from_slice(&[255, 255, 255, alpha]);
+        }
+    }
+    Image::new(
+        Extent3d {
+            width: SIZE,
+            height: SIZE,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    )
+}
+
+fn heart_color(filled: bool) -> Color {
+    if filled {
+        Color::srgb(0.9, 0.1, 0.2)
+    } else {
+        Color::srgba(0.3, 0.3, 0.3, 0.8)
+    }
+}
+
 #[derive(Component)]
 pub struct PopupMessage {
     pub timer: Timer,
@@ -20,7 +116,7 @@ impl PopupMessage {
     pub fn spawn(
         commands: &mut Commands,
         asset_server: &Res<AssetServer>,
-        message: &str,
+        message: impl Into<LocalizedText>,
         duration: f32,
     ) {
         let font = asset_server.load("fonts/montserrat.ttf");
@@ -28,16 +124,15 @@ impl PopupMessage {
             font: font.clone(),
             font_size: 28.0,
             color: Color::WHITE,
-            ..default()
         };
 
         commands
             .spawn(NodeBundle {
+                // Full width so long messages stay centered and wrap on screen
                 style: Style {
                     position_type: PositionType::Absolute,
-                    left: Val::Percent(50.0),
                     top: Val::Percent(50.0),
-                    width: Val::Auto,
+                    width: Val::Percent(100.0),
                     height: Val::Auto,
                     justify_content: JustifyContent::Center,
                     align_items: AlignItems::Center,
@@ -47,7 +142,13 @@ impl PopupMessage {
             })
             .with_children(|parent| {
                 parent.spawn((
-                    TextBundle::from_section(message.to_string(), text_style.clone()),
+                    TextBundle::from_section("", text_style.clone())
+                        .with_text_justify(JustifyText::Center)
+                        .with_style(Style {
+                            max_width: Val::Percent(90.0),
+                            ..default()
+                        }),
+                    message.into(),
                     PopupMessage {
                         timer: Timer::from_seconds(duration, TimerMode::Once),
                     },
@@ -60,12 +161,35 @@ pub struct GameUIPlugin;
 
 impl Plugin for GameUIPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(InGameState::Playing), setup_game_ui)
+        app.init_resource::<HudAssets>()
+            .add_systems(
+                OnEnter(InGameState::Reset),
+                // After the setup, so a resumed run's context is already restored
+                (despawn_game_ui, setup_game_ui)
+                    .chain()
+                    .after(GameplaySet::Setup),
+            )
+            .add_systems(OnExit(AppState::Game), despawn_game_ui)
             .add_systems(
                 Update,
-                (update_game_ui, update_popup_message)
-                    .in_set(GameplaySet::Update)
+                // Presentation only, so these stay on the frame rather than the fixed tick
+                (
+                    update_hearts,
+                    update_score_text,
+                    update_timer_text,
+                    update_door_progress,
+                    update_combo_meter,
+                    update_objective_arrow,
+                    show_gameplay_popups,
+                )
                     .run_if(in_state(InGameState::Playing)),
+            )
+            // Toasts raised as the level is won fade out over the victory screen
+            .add_systems(
+                Update,
+                update_popup_message.run_if(
+                    in_state(InGameState::Playing).or_else(in_state(InGameState::Victory)),
+                ),
             );
     }
 }
@@ -73,6 +197,7 @@ impl Plugin for GameUIPlugin {
 fn setup_game_ui(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
+    hud_assets: Res<HudAssets>,
     game_context: Res<GameContext>,
 ) {
     let font = asset_server.load("fonts/montserrat.ttf");
@@ -83,62 +208,368 @@ fn setup_game_ui(
     };
 
     commands
-        .spawn(NodeBundle {
-            style: Style {
-                width: Val::Percent(100.0),
-                height: Val::Percent(100.0),
-                flex_direction: FlexDirection::Column,
-                justify_content: JustifyContent::FlexStart,
-                align_items: AlignItems::FlexStart,
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::FlexStart,
+                    align_items: AlignItems::FlexStart,
+                    padding: UiRect::all(Val::Px(8.0)),
+                    row_gap: Val::Px(4.0),
+                    ..default()
+                },
                 ..default()
             },
-            ..default()
-        })
+            GameUI,
+        ))
         .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        column_gap: Val::Px(4.0),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|row| {
+                    for index in 0..PLAYER_LIVES {
+                        row.spawn((
+                            ImageBundle {
+                                style: Style {
+                                    width: Val::Px(HUD_ICON_SIZE),
+                                    height: Val::Px(HUD_ICON_SIZE),
+                                    ..default()
+                                },
+                                image: UiImage::new(hud_assets.heart.clone())
+                                    .with_color(heart_color(index < game_context.lives)),
+                                ..default()
+                            },
+                            Heart(index),
+                        ));
+                    }
+                });
             parent.spawn((
-                TextBundle::from_section(
-                    format!("Lives: {}", game_context.lives),
-                    text_style.clone(),
-                ),
-                LivesText,
+                TextBundle::from_section("", text_style.clone()),
+                LocalizedText::new("hud.score").with_arg("score", game_context.score),
+                ScoreText,
             ));
             parent.spawn((
-                TextBundle::from_section(
-                    format!("Score: {}", game_context.score),
-                    text_style.clone(),
-                ),
-                ScoreText,
+                TextBundle::from_section("", text_style.clone()),
+                LocalizedText::new("hud.time").with_arg("time", format_time(0.0)),
+                TimerText,
+            ));
+            parent
+                .spawn((
+                    NodeBundle {
+                        style: Style {
+                            display: Display::None,
+                            flex_direction: FlexDirection::Column,
+                            row_gap: Val::Px(4.0),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    DoorProgress,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            "",
+                            TextStyle {
+                                font_size: 20.0,
+                                ..text_style.clone()
+                            },
+                        ),
+                        LocalizedText::new("hud.door_progress"),
+                        DoorProgressText,
+                    ));
+                    spawn_bar(parent, Color::srgb(0.35, 0.75, 0.35), DoorProgressFill);
+                });
+            parent
+                .spawn((
+                    NodeBundle {
+                        style: Style {
+                            display: Display::None,
+                            flex_direction: FlexDirection::Column,
+                            row_gap: Val::Px(4.0),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    ComboMeter,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            "",
+                            TextStyle {
+                                font_size: 20.0,
+                                color: Color::srgb(1.0, 0.8, 0.2),
+                                ..text_style.clone()
+                            },
+                        ),
+                        LocalizedText::new("hud.combo"),
+                        ComboText,
+                    ));
+                    spawn_bar(parent, Color::srgb(1.0, 0.8, 0.2), ComboFill);
+                });
+
+            parent.spawn((
+                ImageBundle {
+                    style: Style {
+                        display: Display::None,
+                        position_type: PositionType::Absolute,
+                        width: Val::Px(HUD_ICON_SIZE),
+                        height: Val::Px(HUD_ICON_SIZE),
+                        ..default()
+                    },
+                    image: UiImage::new(hud_assets.arrow.clone())
+                        .with_color(Color::srgb(1.0, 0.9, 0.2)),
+                    ..default()
+                },
+                ObjectiveArrow,
             ));
         });
 }
 
-fn update_game_ui(
-    mut set: ParamSet<(
-        Query<(&mut Text, &LivesText)>,
-        Query<(&mut Text, &ScoreText)>,
-    )>,
-    game_context: Res<GameContext>,
+// A bordered bar whose fill is resized as a percentage of its width
+fn spawn_bar(parent: &mut ChildBuilder, color: Color, fill: impl Component) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Px(HUD_BAR_WIDTH),
+                height: Val::Px(12.0),
+                border: UiRect::all(Val::Px(2.0)),
+                ..default()
+            },
+            border_color: Color::WHITE.into(),
+            background_color: Color::srgba(0.0, 0.0, 0.0, 0.5).into(),
+            ..default()
+        })
+        .with_children(|bar| {
+            bar.spawn((
+                NodeBundle {
+                    style: Style {
+                        width: Val::Percent(0.0),
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    background_color: color.into(),
+                    ..default()
+                },
+                fill,
+            ));
+        });
+}
+
+fn despawn_game_ui(
+    mut commands: Commands,
+    hud_query: Query<Entity, With<GameUI>>,
+    popup_query: Query<&Parent, With<PopupMessage>>,
 ) {
+    for entity in hud_query.iter().chain(popup_query.iter().map(Parent::get)) {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn update_hearts(mut heart_query: Query<(&Heart, &mut UiImage)>, game_context: Res<GameContext>) {
     if game_context.is_changed() {
-        for (mut text, _) in set.p0().iter_mut() {
-            text.sections[0].value = format!("Lives: {}", game_context.lives);
+        for (Heart(index), mut image) in heart_query.iter_mut() {
+            image.color = heart_color(*index < game_context.lives);
+        }
+    }
+}
+
+fn update_score_text(
+    mut score_changed: EventReader<ScoreChanged>,
+    mut score_query: Query<&mut LocalizedText, With<ScoreText>>,
+) {
+    if let Some(event) = score_changed.read().last() {
+        for mut text in score_query.iter_mut() {
+            text.set_arg("score", event.score);
+        }
+    }
+}
+
+fn update_timer_text(
+    run_timer: Res<RunTimer>,
+    mut query: Query<&mut LocalizedText, With<TimerText>>,
+) {
+    if run_timer.is_changed() {
+        for mut text in query.iter_mut() {
+            text.set_arg("time", format_time(run_timer.elapsed));
+        }
+    }
+}
+
+// Progress towards the nearest score door that's still closed
+fn update_door_progress(
+    game_context: Res<GameContext>,
+    player_query: Query<&Transform, With<Player>>,
+    door_query: Query<&Door>,
+    mut progress_query: Query<&mut Style, (With<DoorProgress>, Without<DoorProgressFill>)>,
+    mut text_query: Query<&mut LocalizedText, With<DoorProgressText>>,
+    mut fill_query: Query<&mut Style, With<DoorProgressFill>>,
+) {
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+    let nearest = door_query
+        .iter()
+        .filter(|door| door.state != DoorState::Open)
+        .filter_map(|door| match door.requirement {
+            DoorRequirement::Score(required) => Some((door, required)),
+            _ => None,
+        })
+        .min_by(|(a, _), (b, _)| {
+            let distance = |door: &Door| door.closed_position.distance(player_transform.translation);
+            distance(a).total_cmp(&distance(b))
+        })
+        .map(|(_, required)| required);
+
+    for mut style in progress_query.iter_mut() {
+        let display = if nearest.is_some() {
+            Display::Flex
+        } else {
+            Display::None
+        };
+        if style.display != display {
+            style.display = display;
+        }
+    }
+    let Some(required) = nearest else {
+        return;
+    };
+
+    let score = game_context.score.min(required);
+    for mut text in text_query.iter_mut() {
+        text.set_arg("score", score);
+        text.set_arg("required", required);
+    }
+    for mut style in fill_query.iter_mut() {
+        let width = Val::Percent(score as f32 / required.max(1) as f32 * 100.0);
+        if style.width != width {
+            style.width = width;
+        }
+    }
+}
+
+fn update_combo_meter(
+    combo: Res<Combo>,
+    mut meter_query: Query<&mut Style, (With<ComboMeter>, Without<ComboFill>)>,
+    mut text_query: Query<&mut LocalizedText, With<ComboText>>,
+    mut fill_query: Query<&mut Style, With<ComboFill>>,
+) {
+    if !combo.is_changed() {
+        return;
+    }
+    // A single tile isn't a combo yet
+    let display = if combo.count > 1 {
+        Display::Flex
+    } else {
+        Display::None
+    };
+    for mut style in meter_query.iter_mut() {
+        if style.display != display {
+            style.display = display;
         }
-        for (mut text, _) in set.p1().iter_mut() {
-            text.sections[0].value = format!("Score: {}", game_context.score);
+    }
+    for mut text in text_query.iter_mut() {
+        text.set_arg("multiplier", combo.multiplier());
+        text.set_count(combo.count as i64);
+    }
+    for mut style in fill_query.iter_mut() {
+        style.width = Val::Percent(combo.timer.fraction_remaining() * 100.0);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_objective_arrow(
+    ui_scale: Res<UiScale>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<PlayerCamera>>,
+    player_query: Query<&GlobalTransform, With<Player>>,
+    goal_query: Query<&GlobalTransform, With<WinningTile>>,
+    context: Res<GameContext>,
+    tile_query: Query<&Tile>,
+    door_query: Query<&Door>,
+    mut arrow_query: Query<(&mut Style, &mut Transform), With<ObjectiveArrow>>,
+) {
+    let Ok((mut style, mut transform)) = arrow_query.get_single_mut() else {
+        return;
+    };
+
+    // A closed door whose requirement is met opens when the ball gets there, like a timed key
+    // door that shut behind a held key
+    let reachable = door_query.iter().all(|door| {
+        door.state == DoorState::Open || requirement_met(&door.requirement, &context, &tile_query)
+    });
+    let placement = (|| {
+        let (camera, camera_transform) = camera_query.get_single().ok()?;
+        let ball = player_query.get_single().ok()?.translation();
+        let goal = goal_query.get_single().ok()?.translation();
+        if !reachable || ball.xz().distance(goal.xz()) < OBJECTIVE_HIDE_DISTANCE {
+            return None;
         }
+        let ball_on_screen = camera.world_to_viewport(camera_transform, ball)?;
+        let goal_on_screen = camera.world_to_viewport(camera_transform, goal)?;
+        let direction = (goal_on_screen - ball_on_screen).try_normalize()?;
+        Some((ball_on_screen + direction * OBJECTIVE_ARROW_DISTANCE, direction))
+    })();
+
+    let Some((position, direction)) = placement else {
+        style.display = Display::None;
+        return;
+    };
+    // Style sizes are scaled by the UI scale, screen positions aren't
+    let half_size = HUD_ICON_SIZE * ui_scale.0 / 2.0;
+    style.display = Display::Flex;
+    style.left = Val::Px((position.x - half_size) / ui_scale.0);
+    style.top = Val::Px((position.y - half_size) / ui_scale.0);
+    // The arrow is drawn pointing up, screen y grows downwards
+    transform.rotation = Quat::from_rotation_z(direction.x.atan2(-direction.y));
+}
+
+fn show_gameplay_popups(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut door_blocked: EventReader<DoorBlocked>,
+    mut key_collected: EventReader<KeyCollected>,
+    mut checkpoint_reached: EventReader<CheckpointReached>,
+    mut bonus_awarded: EventReader<BonusAwarded>,
+) {
+    for event in door_blocked.read() {
+        let message = requirement_message(&event.requirement);
+        PopupMessage::spawn(&mut commands, &asset_server, message, 3.0);
+    }
+    for _ in key_collected.read() {
+        PopupMessage::spawn(&mut commands, &asset_server, "popup.key_picked_up", 2.0);
+    }
+    for _ in checkpoint_reached.read() {
+        PopupMessage::spawn(&mut commands, &asset_server, "popup.checkpoint_reached", 1.5);
+    }
+    for event in bonus_awarded.read() {
+        let key = match event.bonus {
+            Bonus::Row => "popup.row_bonus",
+            Bonus::Region => "popup.region_bonus",
+        };
+        let message = LocalizedText::new(key).with_arg("points", event.points);
+        PopupMessage::spawn(&mut commands, &asset_server, message, 1.5);
     }
 }
 
 fn update_popup_message(
     mut commands: Commands,
     time: Res<Time>,
-    mut query: Query<(Entity, &mut Text, &mut PopupMessage)>,
+    mut query: Query<(&Parent, &mut Text, &mut PopupMessage)>,
 ) {
-    for (entity, mut text, mut popup_message) in query.iter_mut() {
+    for (parent, mut text, mut popup_message) in query.iter_mut() {
         popup_message.timer.tick(time.delta());
 
         if popup_message.timer.finished() {
-            commands.entity(entity).despawn();
+            // Take the positioning node with it
+            commands.entity(parent.get()).despawn_recursive();
         } else {
             let alpha = popup_message.timer.fraction_remaining();
             text.sections[0].style.color.set_alpha(alpha);
@@ -0,0 +1,70 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use super::components::*;
+use super::events::{DeathCause, PlayerDied};
+use super::states::GameplaySet;
+
+// Kinematic obstacles that kill the ball when it touches their sensor
+pub struct HazardPlugin;
+
+impl Plugin for HazardPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            (move_hazards, detect_hazard_collisions).in_set(GameplaySet::Update),
+        );
+    }
+}
+
+fn move_hazards(time: Res<Time>, mut query: Query<(&mut Transform, &mut MovingHazard)>) {
+    for (mut transform, mut hazard) in query.iter_mut() {
+        hazard.elapsed += time.delta_seconds();
+
+        match hazard.motion {
+            HazardMotion::Path { waypoints, speed } => {
+                let Some(target) = waypoints.get(hazard.next_waypoint).copied() else {
+                    continue;
+                };
+                let to_target = target - transform.translation;
+                let step = speed * time.delta_seconds();
+
+                if to_target.length() <= step {
+                    transform.translation = target;
+                    hazard.next_waypoint = (hazard.next_waypoint + 1) % waypoints.len();
+                } else {
+                    transform.translation += to_target.normalize() * step;
+                }
+            }
+            HazardMotion::Spin { speed } => {
+                transform.rotate_y(speed * time.delta_seconds());
+            }
+            HazardMotion::Sweep { axis, amplitude, period } => {
+                let offset = (hazard.elapsed / period * TAU).sin() * amplitude;
+                transform.translation = hazard.origin + axis * offset;
+            }
+        }
+    }
+}
+
+// Checks the overlaps every tick rather than only when a collision starts, so a hazard still
+// touching the ball when its invulnerability wears off kills it
+fn detect_hazard_collisions(
+    rapier_context: Res<RapierContext>,
+    player_query: Query<Entity, (With<Player>, Without<Invulnerable>)>,
+    hazard_query: Query<Entity, With<MovingHazard>>,
+    mut player_died: EventWriter<PlayerDied>,
+) {
+    for player in player_query.iter() {
+        let touched = hazard_query
+            .iter()
+            .any(|hazard| rapier_context.intersection_pair(player, hazard) == Some(true));
+        if touched {
+            player_died.send(PlayerDied {
+                cause: DeathCause::Hazard,
+            });
+        }
+    }
+}
@@ -0,0 +1,258 @@
+use std::collections::BTreeMap;
+
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::input::ButtonState;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::constants::*;
+use super::level::LEVELS;
+use super::localization::LocalizedText;
+use super::replay::ReplayState;
+use super::resources::*;
+use super::run_timer::{finish_run_timer, format_time};
+use super::states::InGameState;
+use super::storage;
+use super::widgets::MenuBuilder;
+
+// Versioned since the combo and bonus scoring, so tables from the old score scale are left behind
+const HIGH_SCORES_KEY: &str = "high_scores_v2";
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct HighScoreEntry {
+    pub name: String,
+    pub score: u32,
+    pub time: f32,
+    pub deaths: u32,
+    // Unix timestamp in seconds
+    pub date: u64,
+}
+
+impl HighScoreEntry {
+    // Higher scores win, faster times break ties
+    fn beats(&self, other: &HighScoreEntry) -> bool {
+        self.score > other.score || (self.score == other.score && self.time < other.time)
+    }
+}
+
+// Top results per level, persisted between sessions
+#[derive(Resource, Serialize, Deserialize, Default, Debug)]
+pub struct HighScores {
+    pub levels: BTreeMap<u32, Vec<HighScoreEntry>>,
+}
+
+impl HighScores {
+    pub fn table(&self, level: u32) -> &[HighScoreEntry] {
+        self.levels.get(&level).map_or(&[], |entries| entries.as_slice())
+    }
+
+    pub fn qualifies(&self, level: u32, entry: &HighScoreEntry) -> bool {
+        let table = self.table(level);
+        table.len() < HIGH_SCORE_SLOTS || table.iter().any(|existing| entry.beats(existing))
+    }
+
+    // Returns the 1-based rank of the new entry, if it made the table
+    pub fn insert(&mut self, level: u32, entry: HighScoreEntry) -> Option<usize> {
+        let table = self.levels.entry(level).or_default();
+        let index = table
+            .iter()
+            .position(|existing| entry.beats(existing))
+            .unwrap_or(table.len());
+        table.insert(index, entry);
+        table.truncate(HIGH_SCORE_SLOTS);
+        (index < HIGH_SCORE_SLOTS).then_some(index + 1)
+    }
+
+    pub fn save(&self) {
+        storage::save(HIGH_SCORES_KEY, self);
+    }
+}
+
+// Name entry shown on the victory and game over screens when the run made the table
+pub struct HighScorePlugin;
+
+#[derive(Component)]
+struct NameEntryUI;
+
+#[derive(Component)]
+pub struct NameEntry {
+    name: String,
+    entry: HighScoreEntry,
+    level: u32,
+}
+
+#[derive(Component)]
+struct NameEntryText;
+
+impl Plugin for HighScorePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(storage::load::<HighScores>(HIGH_SCORES_KEY).unwrap_or_default())
+            .add_systems(
+                OnEnter(InGameState::Victory),
+                setup_name_entry.after(finish_run_timer),
+            )
+            .add_systems(OnEnter(InGameState::GameOver), setup_name_entry)
+            .add_systems(
+                Update,
+                name_entry_input
+                    .run_if(in_state(InGameState::Victory).or_else(in_state(InGameState::GameOver))),
+            )
+            .add_systems(OnExit(InGameState::Victory), despawn_name_entry)
+            .add_systems(OnExit(InGameState::GameOver), despawn_name_entry);
+    }
+}
+
+// Run condition for screens that shouldn't react to keys typed into the name
+pub fn name_entry_active(query: Query<(), With<NameEntry>>) -> bool {
+    !query.is_empty()
+}
+
+fn setup_name_entry(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    high_scores: Res<HighScores>,
+    context: Res<GameContext>,
+    run_timer: Res<RunTimer>,
+    replay_state: Res<ReplayState>,
+) {
+    if replay_state.is_playback() {
+        return;
+    }
+
+    let entry = HighScoreEntry {
+        name: String::new(),
+        score: context.score,
+        time: run_timer.elapsed,
+        deaths: context.deaths,
+        date: storage::now(),
+    };
+    if context.score == 0 || !high_scores.qualifies(context.level, &entry) {
+        return;
+    }
+
+    let font = asset_server.load("fonts/montserrat.ttf");
+    let text_style = TextStyle {
+        font: font.clone(),
+        font_size: 28.0,
+        color: Color::WHITE,
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(20.0),
+                    width: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            NameEntryUI,
+            NameEntry {
+                name: String::new(),
+                entry,
+                level: context.level,
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section("", text_style.clone()),
+                LocalizedText::new("high_scores.prompt"),
+            ));
+            parent.spawn((
+                TextBundle::from_section("_", text_style.clone()),
+                NameEntryText,
+            ));
+        });
+}
+
+fn name_entry_input(
+    mut commands: Commands,
+    mut keyboard_events: EventReader<KeyboardInput>,
+    mut entry_query: Query<(Entity, &mut NameEntry)>,
+    mut text_query: Query<(Entity, &mut Text), With<NameEntryText>>,
+    mut high_scores: ResMut<HighScores>,
+) {
+    let Ok((entity, mut name_entry)) = entry_query.get_single_mut() else {
+        keyboard_events.clear();
+        return;
+    };
+
+    for event in keyboard_events.read() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+
+        match &event.logical_key {
+            Key::Character(characters) => {
+                for character in characters.chars().filter(|c| c.is_alphanumeric()) {
+                    if name_entry.name.chars().count() < MAX_NAME_LENGTH {
+                        name_entry.name.push(character);
+                    }
+                }
+            }
+            Key::Space if name_entry.name.chars().count() < MAX_NAME_LENGTH => {
+                name_entry.name.push(' ');
+            }
+            Key::Backspace => {
+                name_entry.name.pop();
+            }
+            Key::Enter if !name_entry.name.trim().is_empty() => {
+                let mut entry = name_entry.entry.clone();
+                entry.name = name_entry.name.trim().to_string();
+                let message = match high_scores.insert(name_entry.level, entry) {
+                    Some(rank) => LocalizedText::new("high_scores.saved_rank").with_arg("rank", rank),
+                    None => LocalizedText::new("high_scores.saved"),
+                };
+                high_scores.save();
+
+                for (text_entity, _) in text_query.iter() {
+                    commands.entity(text_entity).insert(message.clone());
+                }
+                // Entry is finished, give the keyboard back to the screen behind it
+                commands.entity(entity).remove::<NameEntry>();
+                return;
+            }
+            _ => {}
+        }
+    }
+
+    for (_, mut text) in text_query.iter_mut() {
+        text.sections[0].value = format!("{}_", name_entry.name);
+    }
+}
+
+fn despawn_name_entry(mut commands: Commands, query: Query<Entity, With<NameEntryUI>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+// Leaderboard panel listing every level's table, used by the main menu
+pub fn spawn_leaderboard(menu: &mut MenuBuilder, high_scores: &HighScores) {
+    menu.title("high_scores.title");
+
+    for (index, level) in LEVELS.iter().enumerate() {
+        let table = high_scores.table(index as u32 + 1);
+        menu.label(level.name, ());
+
+        if table.is_empty() {
+            menu.label("high_scores.empty", ());
+        }
+        for (rank, entry) in table.iter().enumerate() {
+            menu.label(
+                LocalizedText::new("high_scores.row")
+                    .with_count(entry.deaths as i64)
+                    .with_arg("rank", rank + 1)
+                    .with_arg("name", &entry.name)
+                    .with_arg("score", entry.score)
+                    .with_arg("time", format_time(entry.time))
+                    .with_arg("date", storage::format_date(entry.date)),
+                (),
+            );
+        }
+    }
+}
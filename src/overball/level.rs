@@ -0,0 +1,248 @@
+use bevy::prelude::*;
+
+use super::components::{DoorRequirement, HazardMotion, TileKind};
+use super::constants::*;
+
+// Tile legend for level layouts
+//  . normal          ~ ice
+//  > < ^ v boost     % crumbling
+//  1-9 teleporter    x hazard
+//  a-i switch        (space) no tile
+pub struct LevelData {
+    // Localization key
+    pub name: &'static str,
+    // One string per row, from -z to +z, centered on the origin
+    pub tiles: &'static [&'static str],
+    pub doors: &'static [DoorSpec],
+    pub keys: &'static [KeySpec],
+    pub hazards: &'static [HazardSpec],
+    pub checkpoints: &'static [CheckpointSpec],
+    pub medals: MedalTimes,
+    pub stars: StarThresholds,
+}
+
+pub struct DoorSpec {
+    pub position: Vec3,
+    pub half_size: Vec3,
+    pub requirement: DoorRequirement,
+    pub close_after: Option<f32>,
+}
+
+pub struct KeySpec {
+    pub id: u32,
+    pub position: Vec3,
+}
+
+pub struct HazardSpec {
+    pub position: Vec3,
+    pub half_size: Vec3,
+    pub motion: HazardMotion,
+}
+
+// Pad on the floor that moves the respawn point when touched
+pub struct CheckpointSpec {
+    pub position: Vec3,
+}
+
+// Finish times in seconds needed for each medal
+pub struct MedalTimes {
+    pub gold: f32,
+    pub silver: f32,
+    pub bronze: f32,
+}
+
+// Score needed for the second and third star, finishing the level earns the first
+pub struct StarThresholds {
+    pub two: u32,
+    pub three: u32,
+}
+
+impl StarThresholds {
+    pub fn stars_for(&self, score: u32) -> u32 {
+        if score >= self.three {
+            3
+        } else if score >= self.two {
+            2
+        } else {
+            1
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Medal {
+    Gold,
+    Silver,
+    Bronze,
+}
+
+impl MedalTimes {
+    pub fn medal_for(&self, time: f32) -> Option<Medal> {
+        if time <= self.gold {
+            Some(Medal::Gold)
+        } else if time <= self.silver {
+            Some(Medal::Silver)
+        } else if time <= self.bronze {
+            Some(Medal::Bronze)
+        } else {
+            None
+        }
+    }
+}
+
+impl Medal {
+    // Localization key for e.g. "Gold medal"
+    pub fn key(&self) -> &'static str {
+        match self {
+            Medal::Gold => "victory.gold_medal",
+            Medal::Silver => "victory.silver_medal",
+            Medal::Bronze => "victory.bronze_medal",
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            Medal::Gold => Color::srgb(1.0, 0.84, 0.0),
+            Medal::Silver => Color::srgb(0.75, 0.75, 0.75),
+            Medal::Bronze => Color::srgb(0.8, 0.5, 0.2),
+        }
+    }
+}
+
+pub const LEVELS: &[LevelData] = &[LevelData {
+    name: "level.1",
+    tiles: &[
+        "x.........x",
+        ".1.~~~~~...",
+        "...~~~~~...",
+        "..%%%..>...",
+        ".....^.....",
+        ".........a.",
+        "...........",
+        "..v....%%..",
+        "...x.....1.",
+        "...........",
+        "x.........x",
+    ],
+    doors: &[
+        DoorSpec {
+            position: DOOR_POSITION,
+            half_size: Vec3::new(0.5, 0.5, 2.5),
+            requirement: DoorRequirement::Score(DOOR_REQUIRED_SCORE),
+            close_after: None,
+        },
+        // Opens once every ice tile is activated
+        DoorSpec {
+            position: Vec3::new(11.5, 0.5, 0.0),
+            half_size: Vec3::new(0.5, 0.5, 2.5),
+            requirement: DoorRequirement::Region {
+                min: IVec2::new(-2, -4),
+                max: IVec2::new(2, -3),
+            },
+            close_after: None,
+        },
+        // Toggled by the switch tile, rolling back over it closes the door again
+        DoorSpec {
+            position: Vec3::new(12.5, 0.5, 0.0),
+            half_size: Vec3::new(0.5, 0.5, 2.5),
+            requirement: DoorRequirement::Switch(1),
+            close_after: None,
+        },
+        DoorSpec {
+            position: Vec3::new(13.5, 0.5, 0.0),
+            half_size: Vec3::new(0.5, 0.5, 2.5),
+            requirement: DoorRequirement::Key(1),
+            close_after: Some(4.0),
+        },
+    ],
+    keys: &[KeySpec {
+        id: 1,
+        position: Vec3::new(-8.0, 0.5, 8.0),
+    }],
+    hazards: &[
+        HazardSpec {
+            position: Vec3::new(-8.0, 0.5, -7.0),
+            half_size: Vec3::new(0.4, 0.4, 0.4),
+            motion: HazardMotion::Path {
+                waypoints: &[
+                    Vec3::new(-8.0, 0.5, -7.0),
+                    Vec3::new(8.0, 0.5, -7.0),
+                    Vec3::new(8.0, 0.5, -9.0),
+                    Vec3::new(-8.0, 0.5, -9.0),
+                ],
+                speed: 2.0,
+            },
+        },
+        HazardSpec {
+            position: Vec3::new(-7.5, 0.5, 0.0),
+            half_size: Vec3::new(2.0, 0.2, 0.2),
+            motion: HazardMotion::Spin { speed: 1.5 },
+        },
+        HazardSpec {
+            position: Vec3::new(14.5, 0.5, 0.0),
+            half_size: Vec3::new(0.3, 0.3, 0.3),
+            motion: HazardMotion::Sweep {
+                axis: Vec3::Z,
+                amplitude: 2.0,
+                period: 3.0,
+            },
+        },
+    ],
+    checkpoints: &[
+        CheckpointSpec {
+            position: Vec3::new(-8.0, 0.1, 6.0),
+        },
+        CheckpointSpec {
+            position: Vec3::new(8.0, 0.1, 0.0),
+        },
+    ],
+    medals: MedalTimes {
+        gold: 45.0,
+        silver: 75.0,
+        bronze: 120.0,
+    },
+    // 2030 for every tile, row and region without a combo, combos push it higher
+    stars: StarThresholds {
+        two: 1500,
+        three: 3000,
+    },
+}];
+
+impl LevelData {
+    // Levels are numbered from 1, like `GameContext::level`
+    pub fn get(level: u32) -> &'static LevelData {
+        let index = (level.max(1) as usize - 1).min(LEVELS.len() - 1);
+        &LEVELS[index]
+    }
+
+    pub fn tile_specs(&self) -> impl Iterator<Item = (Vec3, TileKind)> + '_ {
+        let half_depth = (self.tiles.len() as i32 - 1) / 2;
+        self.tiles.iter().enumerate().flat_map(move |(row, line)| {
+            let half_width = (line.chars().count() as i32 - 1) / 2;
+            line.chars().enumerate().filter_map(move |(column, symbol)| {
+                let kind = tile_kind_from_symbol(symbol)?;
+                let x = column as i32 - half_width;
+                let z = row as i32 - half_depth;
+                Some((Vec3::new(x as f32, 0.1, z as f32), kind))
+            })
+        })
+    }
+}
+
+fn tile_kind_from_symbol(symbol: char) -> Option<TileKind> {
+    match symbol {
+        '.' => Some(TileKind::Normal),
+        '>' => Some(TileKind::Boost { direction: Vec3::X }),
+        '<' => Some(TileKind::Boost { direction: Vec3::NEG_X }),
+        '^' => Some(TileKind::Boost { direction: Vec3::NEG_Z }),
+        'v' => Some(TileKind::Boost { direction: Vec3::Z }),
+        '~' => Some(TileKind::Ice),
+        '%' => Some(TileKind::Crumbling),
+        'x' => Some(TileKind::Hazard),
+        '1'..='9' => symbol.to_digit(10).map(|channel| TileKind::Teleporter { channel }),
+        'a'..='i' => Some(TileKind::Switch {
+            channel: symbol as u32 - 'a' as u32 + 1,
+        }),
+        _ => None,
+    }
+}
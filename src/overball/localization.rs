@@ -0,0 +1,293 @@
+// Localized UI text: per-language string tables loaded as assets, with `{name}` arguments
+// and plural forms picked by count
+use std::collections::HashMap;
+
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, AsyncReadExt, LoadContext};
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::ui::UiSystem;
+use serde::{Deserialize, Serialize};
+
+use super::storage;
+use super::widgets::{MenuBuilder, WidgetActivated, WidgetSet};
+
+const LANGUAGE_KEY: &str = "language";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum Language {
+    #[default]
+    English,
+    Spanish,
+    German,
+}
+
+impl Language {
+    pub const ALL: [Language; 3] = [Language::English, Language::Spanish, Language::German];
+
+    fn code(&self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::Spanish => "es",
+            Language::German => "de",
+        }
+    }
+
+    fn next(self) -> Language {
+        let index = Language::ALL.iter().position(|language| *language == self);
+        Language::ALL[index.map_or(0, |index| (index + 1) % Language::ALL.len())]
+    }
+
+    // All supported languages use the singular for exactly one
+    fn plural_form(&self, count: i64) -> PluralForm {
+        if count == 1 {
+            PluralForm::One
+        } else {
+            PluralForm::Other
+        }
+    }
+}
+
+enum PluralForm {
+    One,
+    Other,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Entry {
+    Text(String),
+    Plural {
+        // Optional special case for a count of zero, e.g. "No lives remaining"
+        #[serde(default)]
+        zero: Option<String>,
+        one: String,
+        other: String,
+    },
+}
+
+#[derive(Asset, TypePath, Debug, Deserialize)]
+pub struct StringTable(HashMap<String, Entry>);
+
+impl StringTable {
+    fn template(&self, key: &str, language: Language, count: Option<i64>) -> Option<&str> {
+        match self.0.get(key)? {
+            Entry::Text(text) => Some(text),
+            Entry::Plural { zero, one, other } => {
+                let count = count.unwrap_or_default();
+                if count == 0 {
+                    if let Some(zero) = zero {
+                        return Some(zero);
+                    }
+                }
+                match language.plural_form(count) {
+                    PluralForm::One => Some(one),
+                    PluralForm::Other => Some(other),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct StringTableLoader;
+
+impl AssetLoader for StringTableLoader {
+    type Asset = StringTable;
+    type Settings = ();
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext<'_>,
+    ) -> Result<StringTable, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["strings.json"]
+    }
+}
+
+#[derive(Resource)]
+pub struct Locale {
+    pub language: Language,
+    tables: HashMap<Language, Handle<StringTable>>,
+}
+
+impl FromWorld for Locale {
+    fn from_world(world: &mut World) -> Self {
+        let asset_server = world.resource::<AssetServer>();
+        Locale {
+            language: storage::load(LANGUAGE_KEY).unwrap_or_default(),
+            tables: Language::ALL
+                .into_iter()
+                .map(|language| {
+                    let path = format!("locales/{}.strings.json", language.code());
+                    (language, asset_server.load(path))
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Locale {
+    pub fn is_loaded(&self, asset_server: &AssetServer) -> bool {
+        [self.language, Language::default()].iter().all(|language| {
+            self.tables
+                .get(language)
+                .is_some_and(|handle| asset_server.is_loaded_with_dependencies(handle))
+        })
+    }
+
+    // Falls back to English, then to the key itself, so missing strings stand out
+    fn resolve(&self, tables: &Assets<StringTable>, text: &LocalizedText) -> String {
+        let template = [self.language, Language::default()]
+            .iter()
+            .find_map(|language| {
+                let table = tables.get(self.tables.get(language)?)?;
+                table.template(&text.key, *language, text.count)
+            })
+            .unwrap_or(&text.key);
+
+        let mut resolved = template.to_string();
+        for (name, value) in &text.args {
+            resolved = resolved.replace(&format!("{{{}}}", name), value);
+        }
+        for (name, value) in &text.text_args {
+            resolved = resolved.replace(&format!("{{{}}}", name), &self.resolve(tables, value));
+        }
+        resolved
+    }
+}
+
+// Text that follows the current language. The first section of the entity's `Text` is
+// rewritten whenever this or the language changes.
+#[derive(Component, Debug, Clone, Default)]
+pub struct LocalizedText {
+    pub key: String,
+    args: Vec<(String, String)>,
+    // Arguments that are localized themselves, so they change language along with the text
+    text_args: Vec<(String, LocalizedText)>,
+    count: Option<i64>,
+}
+
+impl LocalizedText {
+    pub fn new(key: impl Into<String>) -> Self {
+        LocalizedText {
+            key: key.into(),
+            ..default()
+        }
+    }
+
+    pub fn with_arg(mut self, name: &str, value: impl ToString) -> Self {
+        self.set_arg(name, value);
+        self
+    }
+
+    pub fn with_text_arg(mut self, name: &str, value: LocalizedText) -> Self {
+        self.text_args.push((name.to_string(), value));
+        self
+    }
+
+    // Picks the plural form and fills in `{count}`
+    pub fn with_count(mut self, count: i64) -> Self {
+        self.set_count(count);
+        self
+    }
+
+    pub fn set_arg(&mut self, name: &str, value: impl ToString) {
+        let value = value.to_string();
+        match self.args.iter_mut().find(|(arg, _)| arg == name) {
+            Some((_, current)) => *current = value,
+            None => self.args.push((name.to_string(), value)),
+        }
+    }
+
+    pub fn set_count(&mut self, count: i64) {
+        self.count = Some(count);
+        self.set_arg("count", count);
+    }
+}
+
+impl From<&str> for LocalizedText {
+    fn from(key: &str) -> Self {
+        LocalizedText::new(key)
+    }
+}
+
+// Looks up localized strings for text that isn't kept on a `LocalizedText`
+#[derive(SystemParam)]
+pub struct Localizer<'w> {
+    locale: Res<'w, Locale>,
+    tables: Res<'w, Assets<StringTable>>,
+}
+
+impl Localizer<'_> {
+    pub fn get(&self, text: &LocalizedText) -> String {
+        self.locale.resolve(&self.tables, text)
+    }
+}
+
+#[derive(Component)]
+struct LanguageButton;
+
+pub struct LocalizationPlugin;
+
+impl Plugin for LocalizationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<StringTable>()
+            .init_asset_loader::<StringTableLoader>()
+            .init_resource::<Locale>()
+            .add_systems(
+                Update,
+                (
+                    language_button_input.after(WidgetSet),
+                    save_language.run_if(resource_changed::<Locale>),
+                )
+                    .chain(),
+            )
+            // After everything that spawns or edits text this frame, before it's laid out
+            .add_systems(PostUpdate, update_localized_text.before(UiSystem::Layout));
+    }
+}
+
+// Cycles through the languages, labelled in the current one
+pub fn spawn_language_picker(menu: &mut MenuBuilder) {
+    menu.button("settings.language", LanguageButton);
+}
+
+fn language_button_input(
+    mut activated: EventReader<WidgetActivated>,
+    button_query: Query<(), With<LanguageButton>>,
+    mut locale: ResMut<Locale>,
+) {
+    for WidgetActivated(entity) in activated.read() {
+        if button_query.contains(*entity) {
+            locale.language = locale.language.next();
+        }
+    }
+}
+
+fn save_language(locale: Res<Locale>) {
+    storage::save(LANGUAGE_KEY, &locale.language);
+}
+
+fn update_localized_text(
+    localizer: Localizer,
+    mut table_events: EventReader<AssetEvent<StringTable>>,
+    mut query: Query<(Ref<LocalizedText>, &mut Text)>,
+) {
+    // Tables finishing loading or being hot reloaded count as a language change
+    let refresh_all = localizer.locale.is_changed() || table_events.read().count() > 0;
+
+    for (localized, mut text) in query.iter_mut() {
+        if refresh_all || localized.is_changed() {
+            text.sections[0].value = localizer.get(&localized);
+        }
+    }
+}
@@ -1,20 +1,72 @@
 use bevy::prelude::*;
-use bevy::color::palettes::basic::RED;
-use super::states::AppState;
+use super::states::{AppState, MainMenuSet, MenuState};
+use super::achievements::{spawn_achievement_gallery, AchievementProgress};
+use super::accessibility::{spawn_accessibility_controls, AccessibilitySettings};
+use super::audio::{spawn_volume_controls, AudioSettings};
 use super::constants::*;
+use super::high_scores::{spawn_leaderboard, HighScores};
+use super::level::LEVELS;
+use super::localization::spawn_language_picker;
+use super::resources::{BallAsset, GameContext};
+use super::save_game::{PendingResume, SaveGame};
+use super::widgets::{show_panel, spawn_menu, UiFocus, UiTheme, WidgetActivated};
 // use super::components::*;
 
 #[derive(Component)]
 pub struct MainMenuUI;
 
+// The panel shown while in its menu state
+#[derive(Component, PartialEq)]
+struct MenuPanel(MenuState);
+
+// Slides a panel into place after it's shown
+#[derive(Component)]
+struct PanelSlide {
+    timer: Timer,
+    from: f32,
+}
+
+// Spins behind the menu
+#[derive(Component)]
+struct MenuBall;
+
+#[derive(Component, Clone, Copy)]
+enum MenuButton {
+    Open(MenuState),
+    Continue,
+    NewGame,
+    Level(u32),
+    Back,
+    #[cfg(not(target_arch = "wasm32"))]
+    Quit,
+}
+
+const CONTROLS: [&str; 8] = [
+    "controls.move",
+    "controls.pause",
+    "controls.camera",
+    "controls.zoom",
+    "controls.time",
+    "controls.speed",
+    "controls.mute",
+    "controls.replay",
+];
+
+const CREDITS: [&str; 4] = [
+    "credits.game",
+    "credits.engine",
+    "credits.font",
+    "credits.thanks",
+];
 
 pub struct MainMenuPlugin;
 
 impl Plugin for MainMenuPlugin {
     fn build(&self, app: &mut App) {
-        app.configure_sets(
-            Update, MainMenuSet::Update.run_if(in_state(AppState::Title)),
-        );
+        app.add_sub_state::<MenuState>()
+            .configure_sets(
+                Update, MainMenuSet::Update.run_if(in_state(AppState::Title)),
+            );
 
         app.configure_sets(OnEnter(AppState::Title), MainMenuSet::Setup)
         .configure_sets(OnExit(AppState::Title), MainMenuSet::Cleanup)
@@ -23,7 +75,16 @@ impl Plugin for MainMenuPlugin {
             OnEnter(AppState::Title),
             (setup_main_menu_ui,).in_set(MainMenuSet::Setup),
         )
-        .add_systems(Update, (start_button_system, keyboard_input_system).in_set(MainMenuSet::Update))
+        .add_systems(
+            Update,
+            (
+                (menu_button_system, keyboard_input_system),
+                show_menu_panel.run_if(state_changed::<MenuState>),
+                (slide_panels, spin_menu_ball),
+            )
+                .chain()
+                .in_set(MainMenuSet::Update),
+        )
         .add_systems(
             OnExit(AppState::Title),
             (despawn_main_menu,).in_set(MainMenuSet::Cleanup),
@@ -31,78 +92,112 @@ impl Plugin for MainMenuPlugin {
     }
 }
 
-#[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone)]
-enum MainMenuSet {
-    Setup,
-    Update,
-    Cleanup,
-}
+fn setup_main_menu_ui(
+    mut commands: Commands,
+    theme: Res<UiTheme>,
+    ball_asset: Res<BallAsset>,
+    high_scores: Res<HighScores>,
+    achievements: Res<AchievementProgress>,
+    audio_settings: Res<AudioSettings>,
+    accessibility_settings: Res<AccessibilitySettings>,
+) {
+    // The ball turns slowly behind the menu
+    commands.spawn((
+        Camera3dBundle {
+            transform: Transform::from_xyz(0.0, 0.0, 1.8).looking_at(Vec3::ZERO, Vec3::Y),
+            ..default()
+        },
+        MainMenuUI,
+    ));
+    commands.spawn((
+        DirectionalLightBundle {
+            transform: Transform::from_xyz(1.0, 2.0, 3.0).looking_at(Vec3::ZERO, Vec3::Y),
+            ..default()
+        },
+        MainMenuUI,
+    ));
+    commands.spawn((
+        SceneBundle {
+            scene: ball_asset.model.clone(),
+            transform: Transform::from_rotation(Quat::from_rotation_x(0.4)),
+            ..default()
+        },
+        MenuBall,
+        MainMenuUI,
+    ));
 
-fn setup_main_menu_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
-    let font = asset_server.load("fonts/montserrat.ttf");
-    let text_style = TextStyle {
-        font: font.clone(),
-        font_size: 60.0,
-        color: Color::WHITE,
-    };
+    spawn_menu(&mut commands, &theme, Color::NONE, MainMenuUI, |menu| {
+        menu.panel(true, MenuPanel(MenuState::Main), |menu| {
+            menu.title("main_menu.title");
+            menu.button("main_menu.play", MenuButton::Open(MenuState::Play));
+            menu.button("main_menu.options", MenuButton::Open(MenuState::Options));
+            menu.button("main_menu.controls", MenuButton::Open(MenuState::Controls));
+            menu.button("main_menu.leaderboard", MenuButton::Open(MenuState::Leaderboard));
+            menu.button("main_menu.achievements", MenuButton::Open(MenuState::Achievements));
+            menu.button("main_menu.credits", MenuButton::Open(MenuState::Credits));
+            // Closing the tab is how you leave on the web
+            #[cfg(not(target_arch = "wasm32"))]
+            menu.button("main_menu.quit", MenuButton::Quit);
+        });
+
+        menu.panel(false, MenuPanel(MenuState::Play), |menu| {
+            menu.title("main_menu.play");
+            if SaveGame::load().is_some() {
+                menu.button("main_menu.continue", MenuButton::Continue);
+            }
+            menu.button("main_menu.new_game", MenuButton::NewGame);
+            menu.button("main_menu.level_select", MenuButton::Open(MenuState::LevelSelect));
+            menu.button("menu.back", MenuButton::Back);
+        });
+
+        menu.panel(false, MenuPanel(MenuState::LevelSelect), |menu| {
+            menu.title("main_menu.level_select");
+            for (index, level) in LEVELS.iter().enumerate() {
+                menu.button(level.name, MenuButton::Level(index as u32 + 1));
+            }
+            menu.button("menu.back", MenuButton::Back);
+        });
+
+        menu.panel(false, MenuPanel(MenuState::Options), |menu| {
+            menu.title("main_menu.options");
+            spawn_volume_controls(menu, &audio_settings);
+            spawn_language_picker(menu);
+            menu.button("settings.accessibility", MenuButton::Open(MenuState::Accessibility));
+            menu.button("menu.back", MenuButton::Back);
+        });
+
+        menu.panel(false, MenuPanel(MenuState::Accessibility), |menu| {
+            menu.title("accessibility.title");
+            spawn_accessibility_controls(menu, &accessibility_settings);
+            menu.button("menu.back", MenuButton::Back);
+        });
+
+        menu.panel(false, MenuPanel(MenuState::Controls), |menu| {
+            menu.title("main_menu.controls");
+            for line in CONTROLS {
+                menu.label(line, ());
+            }
+            menu.button("menu.back", MenuButton::Back);
+        });
 
-    commands.spawn((Camera2dBundle::default(), MainMenuUI));
-
-    commands
-        .spawn((
-            NodeBundle {
-                style: Style {
-                    width: Val::Percent(100.0),
-                    height: Val::Percent(100.0),
-                    justify_content: JustifyContent::Center,
-                    align_items: AlignItems::Center,
-                    ..Default::default()
-                },
-                background_color: BackgroundColor::from(Color::NONE),
-                ..Default::default()
-            },
-            MainMenuUI,
-        ))
-        .with_children(|parent| {
-            parent
-                .spawn(NodeBundle {
-                    style: Style {
-                        width: Val::Percent(40.0),
-                        height: Val::Percent(40.0),
-                        justify_content: JustifyContent::FlexStart,
-                        align_items: AlignItems::Center,
-                        flex_direction: FlexDirection::Column,
-                        row_gap: Val::Px(20.0),
-                        ..default()
-                    },
-                    background_color: BackgroundColor::from(Color::srgba(0.4, 0.4, 0.4, 0.5)),
-                    ..default()
-                })
-                .with_children(|parent| {
-                    parent.spawn(TextBundle::from_section(
-                        "Overball Game",
-                        text_style.clone(),
-                    ));
-                    parent
-                        .spawn(ButtonBundle {
-                            style: Style {
-                                width: Val::Px(150.0),
-                                height: Val::Px(65.0),
-                                border: UiRect::all(Val::Px(5.0)),
-                                justify_content: JustifyContent::Center,
-                                align_items: AlignItems::Center,
-                                ..default()
-                            },
-                            border_color: BorderColor(Color::BLACK),
-                            border_radius: BorderRadius::MAX,
-                            background_color: NORMAL_BUTTON.into(),
-                            ..default()
-                        })
-                        .with_children(|parent| {
-                            parent.spawn(TextBundle::from_section("Start", text_style.clone()));
-                        });
-                });
+        menu.panel(false, MenuPanel(MenuState::Leaderboard), |menu| {
+            spawn_leaderboard(menu, &high_scores);
+            menu.button("menu.back", MenuButton::Back);
         });
+
+        menu.panel(false, MenuPanel(MenuState::Achievements), |menu| {
+            spawn_achievement_gallery(menu, &achievements);
+            menu.button("menu.back", MenuButton::Back);
+        });
+
+        menu.panel(false, MenuPanel(MenuState::Credits), |menu| {
+            menu.title("main_menu.credits");
+            for line in CREDITS {
+                menu.label(line, ());
+            }
+            menu.button("menu.back", MenuButton::Back);
+        });
+    });
 }
 
 fn despawn_main_menu(mut commands: Commands, query: Query<Entity, With<MainMenuUI>>) {
@@ -111,36 +206,44 @@ fn despawn_main_menu(mut commands: Commands, query: Query<Entity, With<MainMenuU
     }
 }
 
-fn start_button_system(
-    mut interaction_query: Query<
-        (
-            &Interaction,
-            &mut BackgroundColor,
-            &mut BorderColor,
-            &Children,
-        ),
-        (Changed<Interaction>, With<Button>),
-    >,
-    mut text_query: Query<&mut Text>,
+#[allow(clippy::too_many_arguments)]
+fn menu_button_system(
+    mut activated: EventReader<WidgetActivated>,
+    button_query: Query<&MenuButton>,
+    menu_state: Res<State<MenuState>>,
+    mut next_menu_state: ResMut<NextState<MenuState>>,
     mut state: ResMut<NextState<AppState>>,
+    mut context: ResMut<GameContext>,
+    mut pending_resume: ResMut<PendingResume>,
+    #[cfg(not(target_arch = "wasm32"))] mut exit: EventWriter<AppExit>,
 ) {
-    for (interaction, mut color, mut border_color, children) in &mut interaction_query {
-        let mut text = text_query.get_mut(children[0]).unwrap();
-        match *interaction {
-            Interaction::Pressed => {
-                *color = PRESSED_BUTTON.into();
-                border_color.0 = RED.into();
-                // set game state to Game
+    for WidgetActivated(entity) in activated.read() {
+        let Ok(action) = button_query.get(*entity) else {
+            continue;
+        };
+        match action {
+            MenuButton::Open(menu) => next_menu_state.set(*menu),
+            MenuButton::Back => {
+                if let Some(parent) = menu_state.get().parent() {
+                    next_menu_state.set(parent);
+                }
+            }
+            // Rebuild the saved level once the game starts
+            MenuButton::Continue => {
+                pending_resume.0 = SaveGame::load();
                 state.set(AppState::Game);
             }
-            Interaction::Hovered => {
-                *color = HOVERED_BUTTON.into();
-                border_color.0 = Color::WHITE;
+            MenuButton::NewGame => {
+                context.level = 1;
+                state.set(AppState::Game);
             }
-            Interaction::None => {
-                text.sections[0].value = "Start".to_string();
-                *color = NORMAL_BUTTON.into();
-                border_color.0 = Color::BLACK;
+            MenuButton::Level(level) => {
+                context.level = *level;
+                state.set(AppState::Game);
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            MenuButton::Quit => {
+                exit.send(AppExit::Success);
             }
         }
     }
@@ -148,9 +251,81 @@ fn start_button_system(
 
 fn keyboard_input_system(
     keyboard_input: Res<ButtonInput<KeyCode>>,
+    menu_state: Res<State<MenuState>>,
+    mut next_menu_state: ResMut<NextState<MenuState>>,
     mut state: ResMut<NextState<AppState>>,
+    mut context: ResMut<GameContext>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::Space) {
+    // Space is a quick new game from the top of the menu
+    if keyboard_input.just_pressed(KeyCode::Space) && *menu_state.get() == MenuState::Main {
+        context.level = 1;
         state.set(AppState::Game);
     }
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        if let Some(parent) = menu_state.get().parent() {
+            next_menu_state.set(parent);
+        }
+    }
+}
+
+fn show_menu_panel(
+    mut commands: Commands,
+    menu_state: Res<State<MenuState>>,
+    settings: Res<AccessibilitySettings>,
+    mut panel_query: Query<(&MenuPanel, &mut Style)>,
+    entity_query: Query<(Entity, &MenuPanel)>,
+    mut focus: ResMut<UiFocus>,
+    mut previous_state: Local<Option<MenuState>>,
+) {
+    let shown = *menu_state.get();
+    let previous = previous_state.replace(shown);
+    show_panel(&mut panel_query, MenuPanel(shown), &mut focus);
+
+    if settings.reduced_motion {
+        return;
+    }
+    // Deeper menus come in from the right, going back comes in from the left
+    let going_back = previous.and_then(|previous| previous.parent()) == Some(shown);
+    let from = if going_back {
+        -MENU_SLIDE_DISTANCE
+    } else {
+        MENU_SLIDE_DISTANCE
+    };
+    for (entity, panel) in entity_query.iter() {
+        if *panel == MenuPanel(shown) {
+            commands.entity(entity).insert(PanelSlide {
+                timer: Timer::from_seconds(MENU_TRANSITION_TIME, TimerMode::Once),
+                from,
+            });
+        }
+    }
+}
+
+fn slide_panels(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut panel_query: Query<(Entity, &mut Style, &mut PanelSlide)>,
+) {
+    for (entity, mut style, mut slide) in panel_query.iter_mut() {
+        slide.timer.tick(time.delta());
+        // Eases out, fast at first and settling into place
+        let remaining = slide.timer.fraction_remaining().powi(3);
+        style.left = Val::Px(slide.from * remaining);
+        if slide.timer.finished() {
+            commands.entity(entity).remove::<PanelSlide>();
+        }
+    }
+}
+
+fn spin_menu_ball(
+    time: Res<Time>,
+    settings: Res<AccessibilitySettings>,
+    mut ball_query: Query<&mut Transform, With<MenuBall>>,
+) {
+    if settings.reduced_motion {
+        return;
+    }
+    for mut transform in ball_query.iter_mut() {
+        transform.rotate_y(MENU_BALL_SPIN_SPEED * time.delta_seconds());
+    }
 }
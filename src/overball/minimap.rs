@@ -0,0 +1,231 @@
+use bevy::prelude::*;
+use bevy::render::primitives::Aabb;
+
+use super::accessibility::AccessibilitySettings;
+use super::components::*;
+use super::constants::*;
+use super::states::{AppState, InGameState};
+
+// Corner map of the level showing its tiles, doors, the goal and the ball
+pub struct MinimapPlugin;
+
+#[derive(Component)]
+struct Minimap;
+
+// A map element drawn for `source`, covering `area` of the world's x/z plane
+#[derive(Component)]
+struct MinimapCell {
+    source: Entity,
+    area: Rect,
+}
+
+#[derive(Component)]
+struct MinimapBall;
+
+// World area the minimap covers, grown to fit every cell
+#[derive(Resource, Default)]
+struct MinimapBounds(Rect);
+
+impl MinimapBounds {
+    // Percentages of the map from its top left corner, -z is up like the top down camera
+    fn to_map(&self, point: Vec2) -> Vec2 {
+        (point - self.0.min) / self.0.size() * 100.0
+    }
+}
+
+impl Plugin for MinimapPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MinimapBounds>()
+            .add_systems(
+                OnEnter(InGameState::Reset),
+                (despawn_minimap, spawn_minimap).chain(),
+            )
+            .add_systems(OnExit(AppState::Game), despawn_minimap)
+            .add_systems(
+                Update,
+                (
+                    add_minimap_cells,
+                    layout_minimap,
+                    color_minimap_cells,
+                    move_minimap_ball,
+                )
+                    .chain()
+                    .run_if(in_state(InGameState::Playing)),
+            );
+    }
+}
+
+fn spawn_minimap(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(8.0),
+                    right: Val::Px(8.0),
+                    width: Val::Px(MINIMAP_WIDTH),
+                    height: Val::Px(MINIMAP_WIDTH),
+                    border: UiRect::all(Val::Px(2.0)),
+                    ..default()
+                },
+                border_color: Color::WHITE.into(),
+                background_color: Color::srgba(0.0, 0.0, 0.0, 0.5).into(),
+                ..default()
+            },
+            Minimap,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        width: Val::Px(8.0),
+                        height: Val::Px(8.0),
+                        // Centered on its position
+                        margin: UiRect::new(Val::Px(-4.0), Val::ZERO, Val::Px(-4.0), Val::ZERO),
+                        ..default()
+                    },
+                    border_radius: BorderRadius::MAX,
+                    background_color: Color::WHITE.into(),
+                    z_index: ZIndex::Local(1),
+                    ..default()
+                },
+                MinimapBall,
+            ));
+        });
+}
+
+fn despawn_minimap(mut commands: Commands, query: Query<Entity, With<Minimap>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+// The map is spawned alongside the HUD, so its cells are picked up as they appear
+fn add_minimap_cells(
+    mut commands: Commands,
+    minimap_query: Query<Entity, With<Minimap>>,
+    goal_query: Query<(Entity, &Transform, Option<&Aabb>), Added<WinningTile>>,
+    tile_query: Query<(Entity, &Tile), Added<Tile>>,
+    door_query: Query<(Entity, &Door, Option<&Aabb>), Added<Door>>,
+) {
+    let Ok(minimap) = minimap_query.get_single() else {
+        return;
+    };
+    let area = |center: Vec3, aabb: Option<&Aabb>| {
+        let half_size = aabb.map_or(Vec2::splat(0.5), |aabb| Vec3::from(aabb.half_extents).xz());
+        Rect::from_center_half_size(center.xz(), half_size)
+    };
+
+    // Later siblings draw on top, so doors go over the tiles and the goal
+    let goals = goal_query
+        .iter()
+        .map(|(entity, transform, aabb)| (entity, area(transform.translation, aabb)));
+    let tiles = tile_query.iter().map(|(entity, tile)| {
+        let area = Rect::from_center_size(tile.position.xz(), Vec2::splat(MINIMAP_TILE_SIZE));
+        (entity, area)
+    });
+    let doors = door_query
+        .iter()
+        .map(|(entity, door, aabb)| (entity, area(door.closed_position, aabb)));
+
+    let cells: Vec<_> = goals.chain(tiles).chain(doors).collect();
+    if cells.is_empty() {
+        return;
+    }
+    commands.entity(minimap).with_children(|parent| {
+        for (source, area) in cells {
+            parent.spawn((
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        ..default()
+                    },
+                    ..default()
+                },
+                MinimapCell { source, area },
+            ));
+        }
+    });
+}
+
+// Fits the map to its cells and keeps its shape, whenever cells are added
+fn layout_minimap(
+    mut bounds: ResMut<MinimapBounds>,
+    added_query: Query<(), Added<MinimapCell>>,
+    mut minimap_query: Query<&mut Style, (With<Minimap>, Without<MinimapCell>)>,
+    mut cell_query: Query<(&MinimapCell, &mut Style)>,
+) {
+    if added_query.is_empty() {
+        return;
+    }
+
+    let area = cell_query
+        .iter()
+        .map(|(cell, _)| cell.area)
+        .reduce(|a, b| a.union(b))
+        .unwrap_or_default();
+    bounds.0 = area.inflate(1.0);
+
+    for mut style in minimap_query.iter_mut() {
+        let size = bounds.0.size();
+        style.height = Val::Px(MINIMAP_WIDTH * size.y / size.x);
+    }
+    for (cell, mut style) in cell_query.iter_mut() {
+        let top_left = bounds.to_map(cell.area.min);
+        let size = cell.area.size() / bounds.0.size() * 100.0;
+        style.left = Val::Percent(top_left.x);
+        style.top = Val::Percent(top_left.y);
+        style.width = Val::Percent(size.x);
+        style.height = Val::Percent(size.y);
+    }
+}
+
+// Follows the tiles being activated or crumbling away and the doors opening
+fn color_minimap_cells(
+    settings: Res<AccessibilitySettings>,
+    tile_query: Query<(&Tile, &Visibility)>,
+    door_query: Query<&Door>,
+    goal_query: Query<(), With<WinningTile>>,
+    mut cell_query: Query<(&MinimapCell, &mut BackgroundColor)>,
+) {
+    for (cell, mut background) in cell_query.iter_mut() {
+        let color = if let Ok((tile, visibility)) = tile_query.get(cell.source) {
+            if *visibility == Visibility::Hidden {
+                Color::NONE
+            } else {
+                settings.palette.tile_color(tile.kind, tile.activated)
+            }
+        } else if let Ok(door) = door_query.get(cell.source) {
+            if door.state == DoorState::Open {
+                Color::NONE
+            } else {
+                settings.palette.door_color(&door.requirement)
+            }
+        } else if goal_query.contains(cell.source) {
+            Color::srgb(0.0, 1.0, 0.0)
+        } else {
+            Color::NONE
+        };
+        background.set_if_neq(color.into());
+    }
+}
+
+fn move_minimap_ball(
+    bounds: Res<MinimapBounds>,
+    player_query: Query<&Transform, With<Player>>,
+    mut ball_query: Query<&mut Style, With<MinimapBall>>,
+) {
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+    if bounds.0.is_empty() {
+        return;
+    }
+    let position = bounds.to_map(player_transform.translation.xz());
+    for mut style in ball_query.iter_mut() {
+        // Clamped so it stays on the map when the ball falls off
+        style.left = Val::Percent(position.x.clamp(0.0, 100.0));
+        style.top = Val::Percent(position.y.clamp(0.0, 100.0));
+    }
+}
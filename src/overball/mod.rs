@@ -4,10 +4,31 @@ pub mod resources;
 pub mod states;
 pub mod constants;
 pub mod util;
+pub mod level;
+pub mod storage;
+pub mod audio;
+pub mod widgets;
+pub mod localization;
+pub mod accessibility;
+pub mod simulation;
+pub mod events;
 
 // Plugins
 pub mod main_menu;
 pub mod pause_menu;
 pub mod game_ui;
+pub mod minimap;
 pub mod game_over;
 pub mod victory;
+pub mod tiles;
+pub mod scoring;
+pub mod doors;
+pub mod hazards;
+pub mod checkpoints;
+pub mod death;
+pub mod camera;
+pub mod run_timer;
+pub mod high_scores;
+pub mod achievements;
+pub mod replay;
+pub mod save_game;
@@ -1,44 +1,124 @@
 use bevy::prelude::*;
-use super::states::InGameState;
+
+use crate::time_control::TimeControl;
+use super::accessibility::{spawn_accessibility_controls, AccessibilitySettings};
+use super::audio::{spawn_volume_controls, AudioSettings};
+use super::constants::*;
+use super::localization::{spawn_language_picker, LocalizedText};
+use super::resources::ControlSettings;
+use super::states::{AppState, InGameState};
+use super::storage;
+use super::systems::despawn_player_and_map;
+use super::widgets::{show_panel, spawn_menu, UiFocus, UiTheme, WidgetActivated};
+
+const CONTROL_SETTINGS_KEY: &str = "control_settings";
 
 pub struct PauseMenuPlugin;
 
+#[derive(Component)]
+struct PauseMenuUI;
+
+#[derive(Component, PartialEq)]
+enum PausePanel {
+    Main,
+    Settings,
+    Accessibility,
+}
+
+#[derive(Component)]
+struct SensitivityText;
+
+#[derive(Component, Clone, Copy, PartialEq)]
+enum PauseButton {
+    Resume,
+    Restart,
+    Settings,
+    Accessibility,
+    Quit,
+    Back,
+    BackToSettings,
+    SensitivityDown,
+    SensitivityUp,
+}
+
 impl Plugin for PauseMenuPlugin {
     fn build(&self, app: &mut App) {
-        app
-        .add_systems(OnEnter(InGameState::Paused), setup_pause_menu)
-        .add_systems(OnExit(InGameState::Paused), despawn_pause_menu)
-        .add_systems(Update, pause_game_input);
+        app.insert_resource(
+            storage::load::<ControlSettings>(CONTROL_SETTINGS_KEY).unwrap_or_default(),
+        )
+        .add_systems(OnEnter(InGameState::Paused), (setup_pause_menu, pause_time))
+        .add_systems(OnExit(InGameState::Paused), (despawn_pause_menu, resume_time))
+        .add_systems(
+            OnTransition {
+                exited: InGameState::Paused,
+                entered: InGameState::Reset,
+            },
+            despawn_player_and_map,
+        )
+        .add_systems(
+            OnTransition {
+                exited: InGameState::Paused,
+                entered: InGameState::NotInGame,
+            },
+            despawn_player_and_map,
+        )
+        .add_systems(
+            Update,
+            (
+                pause_game_input,
+                (pause_button_system, update_sensitivity_text)
+                    .chain()
+                    .run_if(in_state(InGameState::Paused)),
+                save_control_settings.run_if(resource_changed::<ControlSettings>),
+            ),
+        );
     }
 }
 
+fn setup_pause_menu(
+    mut commands: Commands,
+    theme: Res<UiTheme>,
+    audio_settings: Res<AudioSettings>,
+    control_settings: Res<ControlSettings>,
+    accessibility_settings: Res<AccessibilitySettings>,
+) {
+    // translucent background
+    let background = Color::srgba(0.0, 0.0, 0.0, 0.8);
+    spawn_menu(&mut commands, &theme, background, PauseMenuUI, |menu| {
+        menu.panel(true, PausePanel::Main, |menu| {
+            menu.title("pause.title");
+            menu.button("pause.resume", PauseButton::Resume);
+            menu.button("pause.restart", PauseButton::Restart);
+            menu.button("pause.settings", PauseButton::Settings);
+            menu.button("pause.quit", PauseButton::Quit);
+        });
 
-fn setup_pause_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
-    let font = asset_server.load("fonts/montserrat.ttf");
-    let text_style = TextStyle {
-        font,
-        font_size: 50.0,
-        color: Color::WHITE,
-    };
-
-    commands
-        .spawn(NodeBundle {
-            style: Style {
-                width: Val::Percent(100.0),
-                height: Val::Percent(100.0),
-                justify_content: JustifyContent::Center,
-                align_items: AlignItems::Center,
-                ..default()
-            },
-            background_color: BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.8)), // translucent background
-            ..default()
-        })
-        .with_children(|parent| {
-            parent.spawn(TextBundle::from_section(
-                "Paused\nPress ESC to resume",
-                text_style,
-            ));
+        // Hidden until the settings button is pressed
+        menu.panel(false, PausePanel::Settings, |menu| {
+            menu.title("settings.title");
+            spawn_volume_controls(menu, &audio_settings);
+            menu.row(|row| {
+                row.label("settings.sensitivity", ());
+                row.small_button("settings.decrease", PauseButton::SensitivityDown);
+                row.label(sensitivity_label(&control_settings), SensitivityText);
+                row.small_button("settings.increase", PauseButton::SensitivityUp);
+            });
+            spawn_language_picker(menu);
+            menu.button("settings.accessibility", PauseButton::Accessibility);
+            menu.button("menu.back", PauseButton::Back);
+        });
+
+        menu.panel(false, PausePanel::Accessibility, |menu| {
+            menu.title("accessibility.title");
+            spawn_accessibility_controls(menu, &accessibility_settings);
+            menu.button("menu.back", PauseButton::BackToSettings);
         });
+    });
+}
+
+fn sensitivity_label(control_settings: &ControlSettings) -> LocalizedText {
+    LocalizedText::new("settings.sensitivity_value")
+        .with_arg("value", format!("{:.1}", control_settings.sensitivity))
 }
 
 pub fn pause_game_input(
@@ -59,11 +139,75 @@ pub fn pause_game_input(
     }
 }
 
-fn despawn_pause_menu(
-    mut commands: Commands,
-    query: Query<Entity, With<Node>>, // Assuming the pause menu has Node component
+fn pause_button_system(
+    mut activated: EventReader<WidgetActivated>,
+    button_query: Query<&PauseButton>,
+    mut panel_query: Query<(&PausePanel, &mut Style)>,
+    mut focus: ResMut<UiFocus>,
+    mut control_settings: ResMut<ControlSettings>,
+    mut in_game_state: ResMut<NextState<InGameState>>,
+    mut app_state: ResMut<NextState<AppState>>,
 ) {
+    for WidgetActivated(entity) in activated.read() {
+        let Ok(action) = button_query.get(*entity) else {
+            continue;
+        };
+        match action {
+            PauseButton::Resume => in_game_state.set(InGameState::Playing),
+            PauseButton::Restart => in_game_state.set(InGameState::Reset),
+            PauseButton::Quit => {
+                in_game_state.set(InGameState::NotInGame);
+                app_state.set(AppState::Title);
+            }
+            PauseButton::Settings => {
+                show_panel(&mut panel_query, PausePanel::Settings, &mut focus)
+            }
+            PauseButton::Accessibility => {
+                show_panel(&mut panel_query, PausePanel::Accessibility, &mut focus)
+            }
+            PauseButton::Back => show_panel(&mut panel_query, PausePanel::Main, &mut focus),
+            PauseButton::BackToSettings => {
+                show_panel(&mut panel_query, PausePanel::Settings, &mut focus)
+            }
+            PauseButton::SensitivityDown | PauseButton::SensitivityUp => {
+                let step = if *action == PauseButton::SensitivityUp {
+                    SENSITIVITY_STEP
+                } else {
+                    -SENSITIVITY_STEP
+                };
+                control_settings.sensitivity = (control_settings.sensitivity + step)
+                    .clamp(MIN_SENSITIVITY, MAX_SENSITIVITY);
+            }
+        }
+    }
+}
+
+fn update_sensitivity_text(
+    control_settings: Res<ControlSettings>,
+    mut text_query: Query<&mut LocalizedText, With<SensitivityText>>,
+) {
+    if control_settings.is_changed() {
+        for mut text in text_query.iter_mut() {
+            *text = sensitivity_label(&control_settings);
+        }
+    }
+}
+
+fn save_control_settings(control_settings: Res<ControlSettings>) {
+    storage::save(CONTROL_SETTINGS_KEY, &*control_settings);
+}
+
+// Stops physics and every timer along with the gameplay systems
+fn pause_time(mut time_control: ResMut<TimeControl>) {
+    time_control.paused = true;
+}
+
+fn resume_time(mut time_control: ResMut<TimeControl>) {
+    time_control.paused = false;
+}
+
+fn despawn_pause_menu(mut commands: Commands, query: Query<Entity, With<PauseMenuUI>>) {
     for entity in query.iter() {
-        commands.entity(entity).despawn();
+        commands.entity(entity).despawn_recursive();
     }
 }
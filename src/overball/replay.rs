@@ -0,0 +1,333 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+
+use super::components::*;
+use super::game_ui::PopupMessage;
+use super::high_scores::name_entry_active;
+use super::resources::*;
+use super::run_timer::finish_run_timer;
+use super::simulation::{InterpolatedTransform, TickRate};
+use super::states::{no_pending_transition, AppState, GameplaySet, InGameState};
+use super::storage;
+use super::systems::{move_player_when_pressing_keys, read_player_input};
+
+// Playback is deterministic, so a ball this far from the recording means something isn't
+const REPLAY_DIVERGENCE_TOLERANCE: f32 = 1e-4;
+
+// One physics tick: the input held and the ball state before the step
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ReplayFrame {
+    pub input: u8,
+    pub translation: [f32; 3],
+    pub rotation: [f32; 4],
+    pub linear_velocity: [f32; 3],
+    pub angular_velocity: [f32; 3],
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Replay {
+    pub level: u32,
+    pub seed: u64,
+    // Frames are only meaningful at the tick rate they were recorded at
+    #[serde(default = "default_tick_rate")]
+    pub tick_rate: f64,
+    #[serde(default = "default_sensitivity")]
+    pub sensitivity: f32,
+    pub time: f32,
+    // Started from a saved game part way through the level
+    #[serde(default)]
+    pub resumed: bool,
+    pub frames: Vec<ReplayFrame>,
+}
+
+impl Replay {
+    fn storage_key(level: u32) -> String {
+        format!("replay_level_{}", level)
+    }
+
+    pub fn load_best(level: u32) -> Option<Replay> {
+        storage::load(&Self::storage_key(level))
+    }
+
+    pub fn save_best(&self) {
+        storage::save(&Self::storage_key(self.level), self);
+    }
+}
+
+fn default_tick_rate() -> f64 {
+    TickRate::default().0
+}
+
+fn default_sensitivity() -> f32 {
+    ControlSettings::default().sensitivity
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReplayMode {
+    #[default]
+    Recording,
+    Playback,
+}
+
+#[derive(Resource, Default)]
+pub struct ReplayState {
+    pub mode: ReplayMode,
+    // Run being recorded, or the one being played back
+    pub replay: Replay,
+    pub cursor: usize,
+    // Playback has already reported drifting from the recording
+    diverged: bool,
+    // The most recently finished run, available to re-watch
+    pub last_run: Option<Replay>,
+    // Played back on the next reset instead of recording
+    pub pending_playback: Option<Replay>,
+}
+
+impl ReplayState {
+    pub fn is_playback(&self) -> bool {
+        self.mode == ReplayMode::Playback
+    }
+}
+
+#[derive(Component)]
+pub struct Ghost {
+    frames: Vec<(Vec3, Quat)>,
+    cursor: usize,
+}
+
+// Records every run tick by tick, plays runs back, and races the best one as a ghost
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ReplayState>()
+            .init_resource::<PlayerInput>()
+            .init_resource::<GameRng>()
+            .add_systems(
+                OnEnter(InGameState::Reset),
+                (begin_replay, spawn_ghost).chain().after(GameplaySet::Setup),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    read_player_input.run_if(is_recording),
+                    play_back_replay.run_if(is_playing_back),
+                    record_replay.run_if(is_recording),
+                    move_player_when_pressing_keys,
+                    advance_ghost,
+                )
+                    .chain()
+                    .in_set(GameplaySet::Input),
+            )
+            .add_systems(
+                FixedUpdate,
+                hold_physics_outside_play
+                    .before(PhysicsSet::SyncBackend)
+                    .run_if(in_state(AppState::Game)),
+            )
+            .add_systems(
+                OnEnter(InGameState::Victory),
+                finish_replay.after(finish_run_timer),
+            )
+            .add_systems(
+                Update,
+                watch_replay_input
+                    .run_if(in_state(InGameState::Victory))
+                    .run_if(not(name_entry_active)),
+            );
+    }
+}
+
+fn is_recording(replay_state: Res<ReplayState>) -> bool {
+    !replay_state.is_playback()
+}
+
+fn is_playing_back(replay_state: Res<ReplayState>) -> bool {
+    replay_state.is_playback()
+}
+
+// Physics only steps while the ball is in play. The ticks between a death and the respawn
+// depend on the frame timing and record no input, so a replay couldn't reproduce them.
+fn hold_physics_outside_play(
+    in_game_state: Res<State<InGameState>>,
+    next_state: Res<NextState<InGameState>>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+) {
+    let in_play = *in_game_state.get() == InGameState::Playing && no_pending_transition(next_state);
+    if rapier_config.physics_pipeline_active != in_play {
+        rapier_config.physics_pipeline_active = in_play;
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn begin_replay(
+    mut commands: Commands,
+    mut replay_state: ResMut<ReplayState>,
+    mut rng: ResMut<GameRng>,
+    mut player_input: ResMut<PlayerInput>,
+    mut tick_rate: ResMut<TickRate>,
+    control_settings: Res<ControlSettings>,
+    context: Res<GameContext>,
+    asset_server: Res<AssetServer>,
+) {
+    replay_state.cursor = 0;
+    replay_state.diverged = false;
+    *player_input = PlayerInput::default();
+
+    if let Some(replay) = replay_state.pending_playback.take() {
+        replay_state.mode = ReplayMode::Playback;
+        tick_rate.set_if_neq(TickRate(replay.tick_rate));
+        replay_state.replay = replay;
+        PopupMessage::spawn(&mut commands, &asset_server, "popup.watching_replay", 2.0);
+    } else {
+        replay_state.mode = ReplayMode::Recording;
+        replay_state.replay = Replay {
+            level: context.level,
+            seed: storage::now(),
+            tick_rate: tick_rate.0,
+            sensitivity: control_settings.sensitivity,
+            ..default()
+        };
+    }
+
+    rng.0 = StdRng::seed_from_u64(replay_state.replay.seed);
+}
+
+fn record_replay(
+    mut replay_state: ResMut<ReplayState>,
+    player_input: Res<PlayerInput>,
+    player_query: Query<(&Transform, &Velocity), With<Player>>,
+) {
+    if let Ok((transform, velocity)) = player_query.get_single() {
+        replay_state.replay.frames.push(ReplayFrame {
+            input: player_input.0,
+            translation: transform.translation.to_array(),
+            rotation: transform.rotation.to_array(),
+            linear_velocity: velocity.linvel.to_array(),
+            angular_velocity: velocity.angvel.to_array(),
+        });
+    }
+}
+
+// Feeds the recorded input back in, the simulation reproduces the rest
+fn play_back_replay(
+    mut replay_state: ResMut<ReplayState>,
+    mut player_input: ResMut<PlayerInput>,
+    player_query: Query<&Transform, With<Player>>,
+) {
+    let cursor = replay_state.cursor;
+    let Some(frame) = replay_state.replay.frames.get(cursor).cloned() else {
+        // Out of frames, let the ball coast to a stop
+        *player_input = PlayerInput::default();
+        return;
+    };
+    replay_state.cursor += 1;
+    *player_input = PlayerInput(frame.input);
+
+    if let Ok(transform) = player_query.get_single() {
+        let recorded = Vec3::from_array(frame.translation);
+        let drift = transform.translation.distance(recorded);
+        if drift > REPLAY_DIVERGENCE_TOLERANCE && !replay_state.diverged {
+            replay_state.diverged = true;
+            warn!("Replay diverged from the recording by {} at tick {}", drift, cursor);
+        }
+    }
+}
+
+fn finish_replay(mut replay_state: ResMut<ReplayState>, run_timer: Res<RunTimer>) {
+    if replay_state.is_playback() {
+        return;
+    }
+
+    replay_state.replay.time = run_timer.elapsed;
+    let replay = std::mem::take(&mut replay_state.replay);
+    // A resumed run can't be played back or raced as a ghost from the start of the level
+    replay_state.last_run = None;
+    if replay.resumed {
+        return;
+    }
+    if run_timer.personal_best {
+        replay.save_best();
+    }
+    replay_state.last_run = Some(replay);
+}
+
+fn watch_replay_input(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut replay_state: ResMut<ReplayState>,
+    mut next_state: ResMut<NextState<InGameState>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::KeyR) {
+        let replay = if replay_state.is_playback() {
+            Some(replay_state.replay.clone())
+        } else {
+            replay_state.last_run.clone()
+        };
+
+        if replay.is_some() {
+            replay_state.pending_playback = replay;
+            next_state.set(InGameState::Reset);
+        }
+    }
+}
+
+pub fn spawn_ghost(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    replay_state: Res<ReplayState>,
+    context: Res<GameContext>,
+    tick_rate: Res<TickRate>,
+) {
+    // The ghost races the live player, there's nothing to race while watching a replay
+    if replay_state.is_playback() {
+        return;
+    }
+    // A best run recorded at another tick rate would race at the wrong speed
+    let Some(best) = Replay::load_best(context.level)
+        .filter(|best| best.tick_rate == tick_rate.0)
+    else {
+        return;
+    };
+
+    let frames = best
+        .frames
+        .iter()
+        .map(|frame| (Vec3::from_array(frame.translation), Quat::from_array(frame.rotation)))
+        .collect::<Vec<_>>();
+    let Some(&(translation, rotation)) = frames.first() else {
+        return;
+    };
+
+    commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(Sphere::new(BallProperties::default().radius * 2.0)),
+            material: materials.add(StandardMaterial {
+                base_color: Color::srgba(0.7, 0.85, 1.0, 0.35),
+                alpha_mode: AlphaMode::Blend,
+                ..default()
+            }),
+            transform: Transform::from_translation(translation).with_rotation(rotation),
+            ..default()
+        },
+        Ghost { frames, cursor: 0 },
+        InterpolatedTransform::default(),
+        GameMap,
+    ));
+}
+
+fn advance_ghost(mut query: Query<(&mut Transform, &mut Visibility, &mut Ghost)>) {
+    for (mut transform, mut visibility, mut ghost) in query.iter_mut() {
+        match ghost.frames.get(ghost.cursor) {
+            Some(&(translation, rotation)) => {
+                transform.translation = translation;
+                transform.rotation = rotation;
+                ghost.cursor += 1;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+}
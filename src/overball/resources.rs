@@ -1,12 +1,23 @@
+use std::mem::Discriminant;
+
 use bevy::prelude::*;
+use bevy::render::mesh::PlaneMeshBuilder;
+use bevy::utils::HashMap;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
 
+use super::accessibility::ColorPalette;
+use super::components::{BallProperties, TileKind};
 use super::constants::*;
 
-#[derive(Resource, Debug)]
+#[derive(Resource, Debug, Clone, Serialize, Deserialize)]
 pub struct GameContext {
     pub lives: u32,
     pub score: u32,
     pub level: u32,
+    pub deaths: u32,
+    pub keys: Vec<u32>,
 }
 
 impl Default for GameContext {
@@ -15,15 +26,41 @@ impl Default for GameContext {
             lives: PLAYER_LIVES,
             score: 0,
             level: 1,
+            deaths: 0,
+            keys: Vec::new(),
         }
     }
 }
 
 impl GameContext {
+    // Starts the current level over, the level is picked from the title menu
     pub fn reset(&mut self) {
         self.lives = PLAYER_LIVES;
         self.score = 0;
-        self.level = 1;
+        self.deaths = 0;
+        self.keys.clear();
+    }
+}
+
+// Tiles activated in quick succession, broken once the window runs out
+#[derive(Resource, Debug)]
+pub struct Combo {
+    pub count: u32,
+    pub timer: Timer,
+}
+
+impl Default for Combo {
+    fn default() -> Self {
+        Combo {
+            count: 0,
+            timer: Timer::from_seconds(COMBO_WINDOW, TimerMode::Once),
+        }
+    }
+}
+
+impl Combo {
+    pub fn multiplier(&self) -> u32 {
+        (1 + self.count.saturating_sub(1) / COMBO_TILES_PER_STEP).min(COMBO_MAX_MULTIPLIER)
     }
 }
 
@@ -34,9 +71,170 @@ pub struct AudioAssets {
     pub door_thunk_sound: Handle<AudioSource>,
     pub door_opening_sound: Handle<AudioSource>,
     pub victory_sound: Handle<AudioSource>,
+    pub death_sound: Handle<AudioSource>,
+    pub click_sound: Handle<AudioSource>,
 }
 
 #[derive(Resource)]
 pub struct BallAsset {
     pub model: Handle<Scene>,
 }
+
+// Player-tunable controls, persisted between sessions
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ControlSettings {
+    // Multiplier on the force the movement keys push the ball with
+    pub sensitivity: f32,
+}
+
+impl Default for ControlSettings {
+    fn default() -> Self {
+        ControlSettings { sensitivity: 1.0 }
+    }
+}
+
+// Movement keys held during the current physics tick, packed as bits so replays stay small
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlayerInput(pub u8);
+
+impl PlayerInput {
+    pub const FORWARD: u8 = 1 << 0;
+    pub const BACKWARD: u8 = 1 << 1;
+    pub const LEFT: u8 = 1 << 2;
+    pub const RIGHT: u8 = 1 << 3;
+
+    pub fn pressed(&self, button: u8) -> bool {
+        self.0 & button != 0
+    }
+
+    // Normalized movement direction on the XZ plane
+    pub fn direction(&self) -> Vec3 {
+        let mut direction = Vec3::ZERO;
+        if self.pressed(Self::FORWARD) { direction.z -= 1.0; }
+        if self.pressed(Self::BACKWARD) { direction.z += 1.0; }
+        if self.pressed(Self::LEFT) { direction.x -= 1.0; }
+        if self.pressed(Self::RIGHT) { direction.x += 1.0; }
+        direction.normalize_or_zero()
+    }
+}
+
+// Gameplay randomness, reseeded from the replay seed at the start of every run
+#[derive(Resource)]
+pub struct GameRng(pub StdRng);
+
+impl Default for GameRng {
+    fn default() -> Self {
+        GameRng(StdRng::seed_from_u64(0))
+    }
+}
+
+// Speedrun clock for the current run, in seconds
+#[derive(Resource, Default, Debug)]
+pub struct RunTimer {
+    pub elapsed: f32,
+    // Time at which each door started opening
+    pub splits: Vec<f32>,
+    pub finished: bool,
+    pub personal_best: bool,
+}
+
+impl RunTimer {
+    pub fn record_split(&mut self) {
+        if !self.finished {
+            self.splits.push(self.elapsed);
+        }
+    }
+}
+
+// Where the ball comes back after dying, moved by touching checkpoints
+#[derive(Resource, Debug)]
+pub struct RespawnPoint(pub Vec3);
+
+impl Default for RespawnPoint {
+    fn default() -> Self {
+        RespawnPoint(BallProperties::default().position)
+    }
+}
+
+// Fastest finish per level, persisted between sessions
+#[derive(Resource, Serialize, Deserialize, Default, Debug)]
+pub struct BestTimes(pub HashMap<u32, f32>);
+
+// The tile the player ball is currently over, if any
+#[derive(Resource, Default, Debug, PartialEq)]
+pub struct CurrentTile(pub Option<Entity>);
+
+// Grid index of the tiles in the current map, keyed by their integer x/z coordinates
+#[derive(Resource, Default)]
+pub struct TileGrid {
+    cells: HashMap<IVec2, Entity>,
+}
+
+impl TileGrid {
+    pub fn cell(position: Vec3) -> IVec2 {
+        IVec2::new(position.x.round() as i32, position.z.round() as i32)
+    }
+
+    pub fn insert(&mut self, position: Vec3, entity: Entity) {
+        self.cells.insert(Self::cell(position), entity);
+    }
+
+    pub fn tile_at(&self, position: Vec3) -> Option<Entity> {
+        self.cells.get(&Self::cell(position)).copied()
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+    }
+}
+
+// Mesh and materials shared by every tile, so the map renders in batches
+#[derive(Resource)]
+pub struct TileAssets {
+    pub mesh: Handle<Mesh>,
+    palette: ColorPalette,
+    materials: HashMap<(Discriminant<TileKind>, bool), (TileKind, Handle<StandardMaterial>)>,
+}
+
+impl FromWorld for TileAssets {
+    fn from_world(world: &mut World) -> Self {
+        let mut meshes = world.resource_mut::<Assets<Mesh>>();
+        TileAssets {
+            mesh: meshes.add(PlaneMeshBuilder::from_length(0.5)),
+            palette: ColorPalette::default(),
+            materials: HashMap::default(),
+        }
+    }
+}
+
+impl TileAssets {
+    pub fn material(
+        &mut self,
+        kind: TileKind,
+        activated: bool,
+        materials: &mut Assets<StandardMaterial>,
+    ) -> Handle<StandardMaterial> {
+        let palette = self.palette;
+        self.materials
+            .entry((std::mem::discriminant(&kind), activated))
+            .or_insert_with(|| {
+                let material = materials.add(StandardMaterial {
+                    base_color: palette.tile_color(kind, activated),
+                    ..default()
+                });
+                (kind, material)
+            })
+            .1
+            .clone()
+    }
+
+    // Recolors the materials already handed out, so tiles on the map change with it
+    pub fn set_palette(&mut self, palette: ColorPalette, materials: &mut Assets<StandardMaterial>) {
+        self.palette = palette;
+        for ((_, activated), (kind, handle)) in self.materials.iter() {
+            if let Some(material) = materials.get_mut(handle) {
+                material.base_color = palette.tile_color(*kind, *activated);
+            }
+        }
+    }
+}
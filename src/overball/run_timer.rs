@@ -0,0 +1,69 @@
+use bevy::prelude::*;
+
+use super::events::DoorOpened;
+use super::resources::*;
+use super::states::{GameplaySet, InGameState};
+use super::storage;
+
+// Versioned like the high scores, so times from an older way of timing runs can be left behind
+const BEST_TIMES_KEY: &str = "best_times_v1";
+
+// Times each run from the first moment of play until the victory screen
+pub struct RunTimerPlugin;
+
+impl Plugin for RunTimerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RunTimer>()
+            .insert_resource(storage::load::<BestTimes>(BEST_TIMES_KEY).unwrap_or_default())
+            .add_systems(OnEnter(InGameState::Reset), reset_run_timer)
+            .add_systems(OnEnter(InGameState::Victory), finish_run_timer)
+            // Only ticks in `Playing`, so pausing stops the clock
+            .add_systems(
+                FixedUpdate,
+                (tick_run_timer, record_door_splits).in_set(GameplaySet::Update),
+            );
+    }
+}
+
+// Formats seconds as m:ss.cc
+pub fn format_time(seconds: f32) -> String {
+    let centiseconds = (seconds * 100.0).round() as u32;
+    format!(
+        "{}:{:02}.{:02}",
+        centiseconds / 6000,
+        centiseconds / 100 % 60,
+        centiseconds % 100
+    )
+}
+
+pub fn reset_run_timer(mut run_timer: ResMut<RunTimer>) {
+    *run_timer = RunTimer::default();
+}
+
+fn tick_run_timer(time: Res<Time>, mut run_timer: ResMut<RunTimer>) {
+    if !run_timer.finished {
+        run_timer.elapsed += time.delta_seconds();
+    }
+}
+
+// A split for every door that opens
+fn record_door_splits(mut door_opened: EventReader<DoorOpened>, mut run_timer: ResMut<RunTimer>) {
+    for _ in door_opened.read() {
+        run_timer.record_split();
+    }
+}
+
+pub fn finish_run_timer(
+    mut run_timer: ResMut<RunTimer>,
+    mut best_times: ResMut<BestTimes>,
+    context: Res<GameContext>,
+) {
+    run_timer.finished = true;
+
+    let best = best_times.0.entry(context.level).or_insert(f32::MAX);
+    run_timer.personal_best = run_timer.elapsed < *best;
+    if run_timer.personal_best {
+        *best = run_timer.elapsed;
+        storage::save(BEST_TIMES_KEY, &*best_times);
+    }
+}
@@ -0,0 +1,258 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::window::{WindowFocused, WindowOccluded};
+use bevy_rapier3d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::components::*;
+use super::checkpoints::{reset_respawn_point, set_active_checkpoint};
+use super::doors::set_door_state;
+use super::replay::{spawn_ghost, Ghost, ReplayState};
+use super::resources::*;
+use super::run_timer::reset_run_timer;
+use super::states::{GameplaySet, InGameState};
+use super::storage;
+use super::systems::clear_context;
+
+const SAVE_GAME_KEY: &str = "save_game";
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DoorSave {
+    // Doors are matched back up by where they stand when closed
+    pub closed_position: [f32; 3],
+    pub state: DoorState,
+    pub translation: [f32; 3],
+    pub close_timer_elapsed: Option<f32>,
+}
+
+// Snapshot of a level in progress
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SaveGame {
+    pub context: GameContext,
+    pub ball_translation: [f32; 3],
+    pub ball_rotation: [f32; 4],
+    pub ball_linear_velocity: [f32; 3],
+    pub ball_angular_velocity: [f32; 3],
+    pub activated_tiles: Vec<[i32; 2]>,
+    pub doors: Vec<DoorSave>,
+    pub elapsed: f32,
+    pub splits: Vec<f32>,
+    pub seed: u64,
+    #[serde(default)]
+    pub respawn_point: Option<[f32; 3]>,
+}
+
+impl SaveGame {
+    pub fn load() -> Option<SaveGame> {
+        storage::load(SAVE_GAME_KEY)
+    }
+
+    pub fn save(&self) {
+        storage::save(SAVE_GAME_KEY, self);
+    }
+
+    pub fn clear() {
+        storage::remove(SAVE_GAME_KEY);
+    }
+}
+
+// Save picked from the main menu, restored on the next reset
+#[derive(Resource, Default)]
+pub struct PendingResume(pub Option<SaveGame>);
+
+// Whether the window lost focus or was hidden this frame
+#[derive(Resource, Default)]
+struct WindowHidden(bool);
+
+// Autosaves the level on pause and when the window is hidden, and restores it on continue
+pub struct SaveGamePlugin;
+
+impl Plugin for SaveGamePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PendingResume>()
+            .init_resource::<WindowHidden>()
+            .add_systems(
+                OnEnter(InGameState::Reset),
+                (
+                    restore_saved_context
+                        .after(clear_context)
+                        .before(GameplaySet::Setup),
+                    restore_saved_world
+                        .after(GameplaySet::Setup)
+                        .after(reset_run_timer)
+                        .after(reset_respawn_point)
+                        .after(spawn_ghost),
+                ),
+            )
+            .add_systems(OnEnter(InGameState::Paused), write_save_game)
+            // A hidden browser tab may stop updating before the pause goes through, so
+            // the save is written straight away rather than waiting for it
+            .add_systems(
+                Update,
+                (
+                    detect_window_hidden,
+                    (write_save_game, pause_game)
+                        .run_if(in_state(InGameState::Playing))
+                        .run_if(window_hidden),
+                )
+                    .chain(),
+            )
+            // The run is over, there's nothing left to continue
+            .add_systems(OnEnter(InGameState::Victory), clear_save_game)
+            .add_systems(OnEnter(InGameState::GameOver), clear_save_game);
+    }
+}
+
+fn restore_saved_context(pending: Res<PendingResume>, mut context: ResMut<GameContext>) {
+    if let Some(save) = &pending.0 {
+        *context = save.context.clone();
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn restore_saved_world(
+    mut commands: Commands,
+    mut pending: ResMut<PendingResume>,
+    mut run_timer: ResMut<RunTimer>,
+    mut replay_state: ResMut<ReplayState>,
+    mut tile_assets: ResMut<TileAssets>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut player_query: Query<(&mut Transform, &mut Velocity), With<Player>>,
+    mut respawn_point: ResMut<RespawnPoint>,
+    mut tile_query: Query<(&mut Tile, &mut Handle<StandardMaterial>), Without<Checkpoint>>,
+    mut checkpoint_query: Query<(&mut Checkpoint, &Handle<StandardMaterial>)>,
+    mut door_query: Query<(Entity, &mut Door, &mut Transform), Without<Player>>,
+    key_query: Query<(Entity, &DoorKey)>,
+    ghost_query: Query<Entity, With<Ghost>>,
+    context: Res<GameContext>,
+) {
+    let Some(save) = pending.0.take() else {
+        return;
+    };
+
+    if let Ok((mut transform, mut velocity)) = player_query.get_single_mut() {
+        transform.translation = Vec3::from_array(save.ball_translation);
+        transform.rotation = Quat::from_array(save.ball_rotation);
+        velocity.linvel = Vec3::from_array(save.ball_linear_velocity);
+        velocity.angvel = Vec3::from_array(save.ball_angular_velocity);
+    }
+
+    for (mut tile, mut material_handle) in tile_query.iter_mut() {
+        let cell = TileGrid::cell(tile.position);
+        if save.activated_tiles.contains(&cell.to_array()) {
+            tile.activated = true;
+            *material_handle = tile_assets.material(tile.kind, true, &mut materials);
+        }
+    }
+
+    for (entity, mut door, mut transform) in door_query.iter_mut() {
+        let closed_position = door.closed_position.to_array();
+        let Some(door_save) = save
+            .doors
+            .iter()
+            .find(|door_save| door_save.closed_position == closed_position)
+        else {
+            continue;
+        };
+
+        transform.translation = Vec3::from_array(door_save.translation);
+        if let (Some(timer), Some(elapsed)) =
+            (door.close_timer.as_mut(), door_save.close_timer_elapsed)
+        {
+            timer.set_elapsed(Duration::from_secs_f32(elapsed));
+        }
+        set_door_state(&mut commands, entity, &mut door, door_save.state);
+    }
+
+    for (entity, key) in key_query.iter() {
+        if context.keys.contains(&key.id) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+
+    if let Some(position) = save.respawn_point {
+        respawn_point.0 = Vec3::from_array(position);
+        set_active_checkpoint(respawn_point.0, &mut checkpoint_query, &mut materials);
+    }
+
+    run_timer.elapsed = save.elapsed;
+    run_timer.splits = save.splits;
+
+    // Keep recording, but the ghost starts at the beginning of the level
+    replay_state.replay.seed = save.seed;
+    replay_state.replay.resumed = true;
+    for entity in ghost_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn write_save_game(
+    context: Res<GameContext>,
+    run_timer: Res<RunTimer>,
+    replay_state: Res<ReplayState>,
+    respawn_point: Res<RespawnPoint>,
+    player_query: Query<(&Transform, &Velocity), With<Player>>,
+    tile_query: Query<&Tile>,
+    door_query: Query<(&Door, &Transform)>,
+) {
+    // Watching a replay isn't a run of your own
+    if replay_state.is_playback() {
+        return;
+    }
+    let Ok((transform, velocity)) = player_query.get_single() else {
+        return;
+    };
+
+    SaveGame {
+        context: context.clone(),
+        ball_translation: transform.translation.to_array(),
+        ball_rotation: transform.rotation.to_array(),
+        ball_linear_velocity: velocity.linvel.to_array(),
+        ball_angular_velocity: velocity.angvel.to_array(),
+        activated_tiles: tile_query
+            .iter()
+            .filter(|tile| tile.activated)
+            .map(|tile| TileGrid::cell(tile.position).to_array())
+            .collect(),
+        doors: door_query
+            .iter()
+            .map(|(door, transform)| DoorSave {
+                closed_position: door.closed_position.to_array(),
+                state: door.state,
+                translation: transform.translation.to_array(),
+                close_timer_elapsed: door.close_timer.as_ref().map(Timer::elapsed_secs),
+            })
+            .collect(),
+        elapsed: run_timer.elapsed,
+        splits: run_timer.splits.clone(),
+        seed: replay_state.replay.seed,
+        respawn_point: Some(respawn_point.0.to_array()),
+    }
+    .save();
+}
+
+// Reads the window events every frame, so ones sent in menus or while paused don't wait
+// around to pause the game as soon as play resumes
+fn detect_window_hidden(
+    mut focus_events: EventReader<WindowFocused>,
+    mut occlusion_events: EventReader<WindowOccluded>,
+    mut window_hidden: ResMut<WindowHidden>,
+) {
+    let lost_focus = focus_events.read().filter(|event| !event.focused).count() > 0;
+    let hidden = occlusion_events.read().filter(|event| event.occluded).count() > 0;
+    window_hidden.0 = lost_focus || hidden;
+}
+
+// Run condition for the window losing focus or being hidden, e.g. switching browser tabs
+fn window_hidden(window_hidden: Res<WindowHidden>) -> bool {
+    window_hidden.0
+}
+
+fn pause_game(mut next_state: ResMut<NextState<InGameState>>) {
+    next_state.set(InGameState::Paused);
+}
+
+fn clear_save_game() {
+    SaveGame::clear();
+}
@@ -0,0 +1,93 @@
+use bevy::prelude::*;
+
+use super::components::*;
+use super::constants::*;
+use super::events::{Bonus, BonusAwarded, ScoreChanged, TileActivated};
+use super::resources::{Combo, GameContext, TileGrid};
+use super::states::{GameplaySet, InGameState};
+use super::systems::detect_ball_on_tile;
+
+// Tiles score more the longer a combo of them goes on, with bonuses for finishing off a row
+// or a door's region
+pub struct ScoringPlugin;
+
+impl Plugin for ScoringPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Combo>()
+            .add_systems(OnEnter(InGameState::Reset), reset_combo)
+            // Dying breaks the combo
+            .add_systems(OnEnter(InGameState::PlayerDied), reset_combo)
+            .add_systems(
+                FixedUpdate,
+                (tick_combo, award_tile_points)
+                    .chain()
+                    .after(detect_ball_on_tile)
+                    .in_set(GameplaySet::Update),
+            );
+    }
+}
+
+fn reset_combo(mut combo: ResMut<Combo>) {
+    *combo = Combo::default();
+}
+
+fn tick_combo(time: Res<Time>, mut combo: ResMut<Combo>) {
+    if combo.count > 0 && combo.timer.tick(time.delta()).finished() {
+        combo.count = 0;
+    }
+}
+
+fn award_tile_points(
+    mut activated: EventReader<TileActivated>,
+    mut context: ResMut<GameContext>,
+    mut combo: ResMut<Combo>,
+    tile_query: Query<&Tile>,
+    door_query: Query<&Door>,
+    mut score_changed: EventWriter<ScoreChanged>,
+    mut bonus_awarded: EventWriter<BonusAwarded>,
+) {
+    for event in activated.read() {
+        let Ok(activated_tile) = tile_query.get(event.tile) else {
+            continue;
+        };
+
+        combo.count += 1;
+        combo.timer.reset();
+        let mut delta = event.kind.score() * TILE_POINTS * combo.multiplier();
+
+        // Activated tiles only count once, so each bonus goes to the tile that finishes it
+        let cell = TileGrid::cell(activated_tile.position);
+        let finished = |in_area: &dyn Fn(IVec2) -> bool| {
+            tile_query
+                .iter()
+                .filter(|tile| tile.kind.activates() && in_area(TileGrid::cell(tile.position)))
+                .all(|tile| tile.activated)
+        };
+        let mut bonuses = Vec::new();
+        if finished(&|other: IVec2| other.y == cell.y) {
+            bonuses.push((Bonus::Row, ROW_BONUS));
+        }
+        for door in door_query.iter() {
+            let DoorRequirement::Region { min, max } = door.requirement else {
+                continue;
+            };
+            let in_region = |other: IVec2| other.cmpge(min).all() && other.cmple(max).all();
+            if in_region(cell) && finished(&in_region) {
+                bonuses.push((Bonus::Region, REGION_BONUS));
+            }
+        }
+        for (bonus, points) in bonuses {
+            delta += points;
+            bonus_awarded.send(BonusAwarded { bonus, points });
+        }
+
+        if delta == 0 {
+            continue;
+        }
+        context.score += delta;
+        score_changed.send(ScoreChanged {
+            score: context.score,
+            delta,
+        });
+    }
+}
@@ -0,0 +1,109 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use super::constants::PHYSICS_TICK_RATE;
+
+// Rate in Hz that physics and gameplay tick at, independent of the frame rate
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct TickRate(pub f64);
+
+impl Default for TickRate {
+    fn default() -> Self {
+        TickRate(PHYSICS_TICK_RATE)
+    }
+}
+
+impl TickRate {
+    pub fn timestep_mode(&self) -> TimestepMode {
+        TimestepMode::Fixed {
+            dt: (1.0 / self.0) as f32,
+            substeps: 1,
+        }
+    }
+}
+
+// Smooths an entity that only moves on fixed ticks by rendering it between its last two
+// simulated poses. The simulated pose is restored before every tick so physics never
+// sees the interpolated one.
+#[derive(Component, Default)]
+pub struct InterpolatedTransform {
+    previous: Option<Transform>,
+    current: Option<Transform>,
+    // What we last wrote for rendering, to spot teleports made outside the fixed tick
+    rendered: Option<Transform>,
+}
+
+impl InterpolatedTransform {
+    // Something other than the simulation moved the entity, e.g. a respawn
+    fn moved_externally(&self, transform: &Transform) -> bool {
+        self.rendered
+            .or(self.current)
+            .is_some_and(|expected| expected != *transform)
+    }
+
+    fn snap_to(&mut self, transform: Transform) {
+        self.previous = Some(transform);
+        self.current = Some(transform);
+    }
+}
+
+pub struct SimulationPlugin;
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TickRate>()
+            .add_systems(First, apply_tick_rate.run_if(resource_changed::<TickRate>))
+            .add_systems(FixedFirst, restore_simulated_transforms)
+            .add_systems(FixedLast, store_simulated_transforms)
+            .add_systems(Update, interpolate_transforms);
+    }
+}
+
+fn apply_tick_rate(
+    tick_rate: Res<TickRate>,
+    mut fixed_time: ResMut<Time<Fixed>>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+) {
+    fixed_time.set_timestep_hz(tick_rate.0);
+    rapier_config.timestep_mode = tick_rate.timestep_mode();
+}
+
+fn restore_simulated_transforms(
+    mut query: Query<(&mut Transform, &mut GlobalTransform, &mut InterpolatedTransform)>,
+) {
+    for (mut transform, mut global_transform, mut interpolation) in query.iter_mut() {
+        if interpolation.moved_externally(&transform) {
+            interpolation.snap_to(*transform);
+        } else if let Some(current) = interpolation.current {
+            *transform = current;
+        }
+        interpolation.rendered = None;
+        // Rapier compares global transforms against its last writeback to detect teleports
+        *global_transform = GlobalTransform::from(*transform);
+    }
+}
+
+fn store_simulated_transforms(mut query: Query<(&Transform, &mut InterpolatedTransform)>) {
+    for (transform, mut interpolation) in query.iter_mut() {
+        interpolation.previous = interpolation.current.or(Some(*transform));
+        interpolation.current = Some(*transform);
+    }
+}
+
+pub fn interpolate_transforms(
+    fixed_time: Res<Time<Fixed>>,
+    mut query: Query<(&mut Transform, &mut InterpolatedTransform)>,
+) {
+    let alpha = fixed_time.overstep_fraction();
+
+    for (mut transform, mut interpolation) in query.iter_mut() {
+        if interpolation.moved_externally(&transform) {
+            interpolation.snap_to(*transform);
+        }
+        if let (Some(previous), Some(current)) = (interpolation.previous, interpolation.current) {
+            transform.translation = previous.translation.lerp(current.translation, alpha);
+            transform.rotation = previous.rotation.slerp(current.rotation, alpha);
+            interpolation.rendered = Some(*transform);
+        }
+    }
+}
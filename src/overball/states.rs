@@ -19,6 +19,34 @@ pub enum InGameState {
     Victory,
 }
 
+// Screens of the title menu, only present while on the title
+#[derive(SubStates, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[source(AppState = AppState::Title)]
+pub enum MenuState {
+    #[default]
+    Main,
+    Play,
+    LevelSelect,
+    Options,
+    Accessibility,
+    Controls,
+    Leaderboard,
+    Achievements,
+    Credits,
+}
+
+impl MenuState {
+    // Where back and Escape lead, `None` at the top
+    pub fn parent(&self) -> Option<MenuState> {
+        match self {
+            MenuState::Main => None,
+            MenuState::LevelSelect => Some(MenuState::Play),
+            MenuState::Accessibility => Some(MenuState::Options),
+            _ => Some(MenuState::Main),
+        }
+    }
+}
+
 #[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone)]
 pub enum MainMenuSet {
     Setup,
@@ -26,8 +54,17 @@ pub enum MainMenuSet {
     Cleanup,
 }
 
+// Once a tick has decided the outcome, e.g. a death, the frame's remaining ticks wait for
+// the state change, so a run and its replay stop on the same tick
+pub fn no_pending_transition(next_state: Res<NextState<InGameState>>) -> bool {
+    matches!(*next_state, NextState::Unchanged)
+}
+
 #[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone)]
 pub enum GameplaySet {
     Setup,
+    // Fixed-tick input handling, ahead of the physics step
+    Input,
+    // Fixed-tick gameplay rules, after the physics step
     Update,
 }
@@ -0,0 +1,111 @@
+// Small key/value store for persistent data
+// Uses `localStorage` in the browser and JSON files in `saves/` on native
+use bevy::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+pub fn load<T: DeserializeOwned>(key: &str) -> Option<T> {
+    let contents = read(key)?;
+    match serde_json::from_str(&contents) {
+        Ok(value) => Some(value),
+        Err(error) => {
+            warn!("Ignoring corrupt save data for {}: {}", key, error);
+            None
+        }
+    }
+}
+
+pub fn save<T: Serialize>(key: &str, value: &T) {
+    match serde_json::to_string(value) {
+        Ok(contents) => write(key, &contents),
+        Err(error) => warn!("Failed to serialize {}: {}", key, error),
+    }
+}
+
+// Seconds since the unix epoch
+pub fn now() -> u64 {
+    #[cfg(target_arch = "wasm32")]
+    {
+        (js_sys::Date::now() / 1000.0) as u64
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs())
+    }
+}
+
+// Formats a unix timestamp as YYYY-MM-DD (UTC)
+pub fn format_date(timestamp: u64) -> String {
+    // Howard Hinnant's days-to-civil algorithm
+    let days = (timestamp / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read(key: &str) -> Option<String> {
+    local_storage()?.get_item(&storage_key(key)).ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write(key: &str, contents: &str) {
+    if let Some(storage) = local_storage() {
+        if storage.set_item(&storage_key(key), contents).is_err() {
+            warn!("Failed to write {} to localStorage", key);
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn remove(key: &str) {
+    if let Some(storage) = local_storage() {
+        let _ = storage.remove_item(&storage_key(key));
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn storage_key(key: &str) -> String {
+    format!("overball.{}", key)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+const SAVE_DIR: &str = "saves";
+
+#[cfg(not(target_arch = "wasm32"))]
+fn save_path(key: &str) -> std::path::PathBuf {
+    std::path::Path::new(SAVE_DIR).join(format!("{}.json", key))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read(key: &str) -> Option<String> {
+    std::fs::read_to_string(save_path(key)).ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write(key: &str, contents: &str) {
+    let result = std::fs::create_dir_all(SAVE_DIR)
+        .and_then(|_| std::fs::write(save_path(key), contents));
+    if let Err(error) = result {
+        warn!("Failed to write {}: {}", key, error);
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn remove(key: &str) {
+    let _ = std::fs::remove_file(save_path(key));
+}
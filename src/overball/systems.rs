@@ -1,85 +1,97 @@
-use bevy::audio::{PlaybackMode, Volume};
 use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
 use super::components::*;
+use super::events::{DeathCause, LevelCompleted, PlayerDied, TileActivated};
+use super::localization::Locale;
+use super::replay::ReplayState;
 use super::resources::*;
 use super::states::*;
 use super::constants::*;
 
-pub fn move_player_when_pressing_keys(
+pub fn read_player_input(
     keyboard_input: Res<ButtonInput<KeyCode>>,
-    time: Res<Time>,
-    mut query: Query<(&mut Transform, &mut Ball), With<Player>>,
+    mut player_input: ResMut<PlayerInput>,
 ) {
-    for (mut transform, mut ball) in query.iter_mut() {
-        let mut direction = Vec3::ZERO;
-
-        // FORWARDS
-        if keyboard_input.pressed(KeyCode::KeyW) { direction.z -= 1.0; }
-        // BACKWARDS
-        if keyboard_input.pressed(KeyCode::KeyS) { direction.z += 1.0; }
-        // LEFT
-        if keyboard_input.pressed(KeyCode::KeyA) { direction.x -= 1.0; }
-        // RIGHT
-        if keyboard_input.pressed(KeyCode::KeyD) { direction.x += 1.0; }
-
-        // Normalize direction if there is movement
-        if direction != Vec3::ZERO {
-            direction = direction.normalize();
-            ball.velocity += direction * MOVEMENT_SPEED * time.delta_seconds(); // Scale by movement speed
-        }
-
-        // Apply velocity to position
-        let movement = ball.velocity; // Get the movement vector
-        transform.translation += movement * time.delta_seconds();
-
-        // Calculate the amount of rotation based on the distance moved
-        let distance = movement.length() * time.delta_seconds();
-        let rotation_axis = Vec3::new(-movement.z, 0.0, movement.x).normalize(); // Rotation axis perpendicular to movement
+    let mut buttons = 0;
+
+    // FORWARDS
+    if keyboard_input.pressed(KeyCode::KeyW) { buttons |= PlayerInput::FORWARD; }
+    // BACKWARDS
+    if keyboard_input.pressed(KeyCode::KeyS) { buttons |= PlayerInput::BACKWARD; }
+    // LEFT
+    if keyboard_input.pressed(KeyCode::KeyA) { buttons |= PlayerInput::LEFT; }
+    // RIGHT
+    if keyboard_input.pressed(KeyCode::KeyD) { buttons |= PlayerInput::RIGHT; }
+
+    *player_input = PlayerInput(buttons);
+}
 
-        if distance > 0.0 {
-            // Rotate the ball around the axis perpendicular to movement
-            let rotation_angle = -distance / ball.radius; // The amount to rotate
-            let rotation_quat = Quat::from_axis_angle(rotation_axis, rotation_angle);
-            transform.rotation = rotation_quat * transform.rotation;
-        }
+pub fn move_player_when_pressing_keys(
+    player_input: Res<PlayerInput>,
+    control_settings: Res<ControlSettings>,
+    replay_state: Res<ReplayState>,
+    mut query: Query<&mut ExternalForce, With<Player>>,
+) {
+    // Replays push with the sensitivity they were recorded with
+    let sensitivity = if replay_state.is_playback() {
+        replay_state.replay.sensitivity
+    } else {
+        control_settings.sensitivity
+    };
+
+    for mut external_force in query.iter_mut() {
+        let direction = player_input.direction() * sensitivity;
+
+        // Push the ball and spin it around the axis perpendicular to the input,
+        // rapier's contact friction turns the spin into rolling
+        external_force.force = direction * MOVEMENT_FORCE;
+        external_force.torque = Vec3::Y.cross(direction) * ROLLING_TORQUE;
+    }
+}
 
-        // Decrease velocity slowly each frame
-        ball.velocity *= DAMPING_FACTOR.powf(time.delta_seconds());
+// The force is only updated while playing, so it would keep pushing the ball behind the
+// pause menu or the victory screen
+pub fn release_player_force(mut query: Query<&mut ExternalForce, With<Player>>) {
+    for mut external_force in query.iter_mut() {
+        *external_force = ExternalForce::default();
     }
 }
 
 pub fn detect_ball_on_tile(
     ball_query: Query<&Transform, With<Player>>,
-    mut tile_query: Query<(&Transform, &mut Tile, &mut Handle<StandardMaterial>)>,
-    mut context: ResMut<GameContext>,
+    mut tile_query: Query<(Entity, &mut Tile, &mut Handle<StandardMaterial>)>,
+    tile_grid: Res<TileGrid>,
+    mut tile_assets: ResMut<TileAssets>,
+    mut current_tile: ResMut<CurrentTile>,
+    mut tile_activated: EventWriter<TileActivated>,
     mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
     if let Ok(ball_transform) = ball_query.get_single() {
-        let ball_position = ball_transform.translation;
+        let tile_under_ball = tile_grid.tile_at(ball_transform.translation);
 
-        for (tile_transform, mut tile, material_handle) in tile_query.iter_mut() {
-            let tile_position = tile_transform.translation;
-
-            // Check if the ball is on top of the tile
-            if (ball_position.x - tile_position.x).abs() < 0.5 &&
-               (ball_position.z - tile_position.z).abs() < 0.5 {
-                if !tile.activated {
-                    tile.activated = true;
-                    context.score += 1;
-
-                    // Change activated tile to be green
-                    if let Some(material) = materials.get_mut(&*material_handle) {
-                        material.base_color = Color::srgb(0.0, 1.0, 0.0);
-                    }
-                }
+        if let Some((entity, mut tile, mut material_handle)) =
+            tile_under_ball.and_then(|entity| tile_query.get_mut(entity).ok())
+        {
+            if !tile.activated && tile.kind.activates() {
+                tile.activated = true;
+                tile_activated.send(TileActivated {
+                    tile: entity,
+                    kind: tile.kind,
+                });
+
+                // Swap to the shared activated material for this kind
+                *material_handle = tile_assets.material(tile.kind, true, &mut materials);
             }
         }
+
+        // Only flag a change when the ball moves onto a different tile
+        current_tile.set_if_neq(CurrentTile(tile_under_ball));
     }
 }
 
 pub fn check_player_out_of_bounds(
     mut query: Query<(&mut Transform, &mut Ball), With<Player>>,
-    mut next_state: ResMut<NextState<InGameState>>,
+    mut player_died: EventWriter<PlayerDied>,
 ) {
     for (transform, _ball) in query.iter_mut() {
         let position = transform.translation;
@@ -88,32 +100,13 @@ pub fn check_player_out_of_bounds(
             position.y < MIN_Y || position.y > MAX_Y ||
             position.z < MIN_Z || position.z > MAX_Z
         {
-            next_state.set(InGameState::PlayerDied);
+            player_died.send(PlayerDied {
+                cause: DeathCause::OutOfBounds,
+            });
         }
     }
 }
 
-pub fn handle_player_death(
-    mut query: Query<(&mut Transform, &mut Ball), With<Player>>,
-    mut context: ResMut<GameContext>,
-    mut game_state: ResMut<NextState<InGameState>>,
-) {
-    for (mut transform, mut ball) in query.iter_mut() {
-        if context.lives == 0 {
-            game_state.set(InGameState::GameOver);
-        } else {
-            // play respawn sound
-            context.lives -= 1;
-            transform.translation = Vec3::new(0.0, 1.0, 0.0);
-            ball.velocity = Vec3::ZERO;
-            game_state.set(InGameState::Playing);
-        }
-
-    }
-}
-
-
-
 pub fn despawn_player_and_map(
     mut commands: Commands,
     player_query: Query<Entity, With<Player>>,
@@ -140,6 +133,8 @@ pub fn load_audio_assets(mut commands: Commands, asset_server: Res<AssetServer>)
     let door_opening_sound = asset_server.load("sounds/door-opening.mp3");
     let game_over_sound = asset_server.load("sounds/game_over.wav");
     let victory_sound = asset_server.load("sounds/victory.mp3");
+    let death_sound = asset_server.load("sounds/death.wav");
+    let click_sound = asset_server.load("sounds/click.wav");
 
     commands.insert_resource(AudioAssets {
         bg_music,
@@ -147,6 +142,8 @@ pub fn load_audio_assets(mut commands: Commands, asset_server: Res<AssetServer>)
         door_thunk_sound,
         door_opening_sound,
         victory_sound,
+        death_sound,
+        click_sound,
     });
 }
 
@@ -154,11 +151,13 @@ pub fn check_assets_loaded(
     asset_server: Res<AssetServer>,
     audio_assets: Res<AudioAssets>,
     ball_asset: Res<BallAsset>,
+    locale: Res<Locale>,
     mut game_state: ResMut<NextState<AppState>>,
 ) {
     if asset_server.get_load_state(&audio_assets.bg_music) == Some(bevy::asset::LoadState::Loaded)
         && asset_server.get_load_state(&audio_assets.game_over_sound) == Some(bevy::asset::LoadState::Loaded)
         && asset_server.get_load_state(&ball_asset.model) == Some(bevy::asset::LoadState::Loaded)
+        && locale.is_loaded(&asset_server)
     {
         game_state.set(AppState::Title);
     }
@@ -180,8 +179,7 @@ pub fn check_winning_tile(
     player_query: Query<&Transform, With<Player>>,
     winning_tile_query: Query<&Transform, With<WinningTile>>,
     mut timer_query: Query<(Entity, &mut WinningTileTimer)>,
-    mut next_state: ResMut<NextState<InGameState>>,
-    audio_assets: Res<AudioAssets>,
+    mut level_completed: EventWriter<LevelCompleted>,
 ) {
     if let Ok(player_transform) = player_query.get_single() {
         let player_position = player_transform.translation;
@@ -197,15 +195,7 @@ pub fn check_winning_tile(
                 if let Ok((entity, mut timer)) = timer_query.get_single_mut() {
                     timer.0.tick(time.delta());
                     if timer.0.finished() {
-                        commands.spawn(AudioBundle {
-                            source: audio_assets.victory_sound.clone(),
-                            settings: PlaybackSettings {
-                                volume: Volume::new(0.2),
-                                ..default()
-                            },
-                        });
-
-                        next_state.set(InGameState::Victory);
+                        level_completed.send(LevelCompleted);
                         commands.entity(entity).despawn(); // Remove the timer entity
                     }
                 } else {
@@ -223,18 +213,6 @@ pub fn check_winning_tile(
     }
 }
 
-pub fn setup_background_music(mut commands: Commands, audio_assets: Res<AudioAssets>) {
-    commands.spawn(AudioBundle {
-        source: audio_assets.bg_music.clone(),
-        settings: PlaybackSettings {
-            volume: Volume::new(0.2),
-            mode: PlaybackMode::Loop,
-            ..default()
-        },
-        
-    });
-}
-
 pub fn clear_context(mut context: ResMut<GameContext>) {
     context.reset();
 }
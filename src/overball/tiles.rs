@@ -0,0 +1,177 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use super::components::*;
+use super::constants::*;
+use super::events::{DeathCause, PlayerDied};
+use super::resources::{CurrentTile, TileAssets, TileGrid};
+use super::states::{GameplaySet, InGameState};
+use super::systems::detect_ball_on_tile;
+
+// Behaviours for the special tile kinds, triggered when the ball rolls onto a tile
+pub struct TilePlugin;
+
+impl Plugin for TilePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CurrentTile>()
+            .init_resource::<TileGrid>()
+            .init_resource::<TileAssets>()
+            .add_systems(OnEnter(InGameState::Reset), reset_current_tile)
+            .add_systems(
+                FixedUpdate,
+                (
+                    apply_boost_tiles,
+                    apply_ice_tiles,
+                    apply_teleporter_tiles,
+                    trigger_crumbling_tiles,
+                    update_crumbling_tiles,
+                    check_deadly_tiles,
+                )
+                    .chain()
+                    .after(detect_ball_on_tile)
+                    .in_set(GameplaySet::Update),
+            );
+    }
+}
+
+fn reset_current_tile(mut current_tile: ResMut<CurrentTile>) {
+    *current_tile = CurrentTile::default();
+}
+
+fn apply_boost_tiles(
+    current_tile: Res<CurrentTile>,
+    tile_query: Query<&Tile>,
+    mut player_query: Query<&mut ExternalImpulse, With<Player>>,
+) {
+    if !current_tile.is_changed() {
+        return;
+    }
+
+    if let Some(TileKind::Boost { direction }) = current_tile
+        .0
+        .and_then(|entity| tile_query.get(entity).ok())
+        .map(|tile| tile.kind)
+    {
+        for mut impulse in player_query.iter_mut() {
+            impulse.impulse += direction * BOOST_IMPULSE;
+        }
+    }
+}
+
+fn apply_ice_tiles(
+    current_tile: Res<CurrentTile>,
+    tile_query: Query<&Tile>,
+    mut player_query: Query<(&mut Damping, &mut Friction), With<Player>>,
+) {
+    if !current_tile.is_changed() {
+        return;
+    }
+
+    let on_ice = current_tile
+        .0
+        .and_then(|entity| tile_query.get(entity).ok())
+        .is_some_and(|tile| tile.kind == TileKind::Ice);
+
+    for (mut damping, mut friction) in player_query.iter_mut() {
+        if on_ice {
+            damping.linear_damping = ICE_LINEAR_DAMPING;
+            damping.angular_damping = ICE_ANGULAR_DAMPING;
+            friction.coefficient = ICE_FRICTION;
+        } else {
+            damping.linear_damping = BALL_LINEAR_DAMPING;
+            damping.angular_damping = BALL_ANGULAR_DAMPING;
+            friction.coefficient = BALL_FRICTION;
+        }
+    }
+}
+
+fn apply_teleporter_tiles(
+    mut current_tile: ResMut<CurrentTile>,
+    tile_query: Query<(Entity, &Tile)>,
+    mut player_query: Query<&mut Transform, With<Player>>,
+) {
+    if !current_tile.is_changed() {
+        return;
+    }
+
+    let Some(TileKind::Teleporter { channel }) = current_tile
+        .0
+        .and_then(|entity| tile_query.get(entity).ok())
+        .map(|(_, tile)| tile.kind)
+    else {
+        return;
+    };
+
+    // Find the other end of the pair
+    let destination = tile_query.iter().find(|(entity, tile)| {
+        Some(*entity) != current_tile.0 && tile.kind == TileKind::Teleporter { channel }
+    });
+
+    if let Some((destination_entity, destination_tile)) = destination {
+        for mut transform in player_query.iter_mut() {
+            transform.translation.x = destination_tile.position.x;
+            transform.translation.z = destination_tile.position.z;
+        }
+        // Mark the ball as already standing on the destination so it doesn't bounce back
+        current_tile.0 = Some(destination_entity);
+    }
+}
+
+fn trigger_crumbling_tiles(
+    mut commands: Commands,
+    current_tile: Res<CurrentTile>,
+    tile_query: Query<&Tile, Without<CrumblingTile>>,
+) {
+    if !current_tile.is_changed() {
+        return;
+    }
+
+    if let Some(entity) = current_tile.0 {
+        if let Ok(tile) = tile_query.get(entity) {
+            if tile.kind == TileKind::Crumbling {
+                commands.entity(entity).insert(CrumblingTile {
+                    timer: Timer::from_seconds(CRUMBLE_DELAY, TimerMode::Once),
+                    collapsed: false,
+                });
+            }
+        }
+    }
+}
+
+fn update_crumbling_tiles(
+    time: Res<Time>,
+    mut query: Query<(&mut Transform, &mut Visibility, &mut CrumblingTile)>,
+) {
+    for (mut transform, mut visibility, mut crumbling) in query.iter_mut() {
+        if !crumbling.collapsed {
+            crumbling.timer.tick(time.delta());
+            crumbling.collapsed = crumbling.timer.finished();
+        } else if *visibility != Visibility::Hidden {
+            // Let the tile drop out of the floor, then hide it
+            transform.translation.y -= CRUMBLE_FALL_SPEED * time.delta_seconds();
+            if transform.translation.y < -1.0 {
+                *visibility = Visibility::Hidden;
+            }
+        }
+    }
+}
+
+// Hazards and collapsed tiles both kill the ball
+fn check_deadly_tiles(
+    current_tile: Res<CurrentTile>,
+    tile_query: Query<(&Tile, Option<&CrumblingTile>)>,
+    invulnerable_query: Query<(), (With<Player>, With<Invulnerable>)>,
+    mut player_died: EventWriter<PlayerDied>,
+) {
+    if !invulnerable_query.is_empty() {
+        return;
+    }
+    if let Some((tile, crumbling)) = current_tile.0.and_then(|entity| tile_query.get(entity).ok()) {
+        let collapsed = crumbling.is_some_and(|crumbling| crumbling.collapsed);
+        if tile.kind == TileKind::Hazard || collapsed {
+            player_died.send(PlayerDied {
+                cause: DeathCause::Tile,
+            });
+        }
+    }
+}
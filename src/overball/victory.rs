@@ -1,114 +1,267 @@
 use bevy::prelude::*;
-//use bevy::audio::Volume; TODO
 use super::states::*;
+use super::audio::{PlaySfx, Sfx};
+use super::components::Tile;
 use super::constants::*;
 use super::resources::*;
+use super::level::{LevelData, Medal, LEVELS};
+use super::localization::LocalizedText;
+use super::run_timer::{finish_run_timer, format_time};
 use super::systems::despawn_player_and_map;
+use super::widgets::{spawn_menu, UiTheme, WidgetActivated};
 
 pub struct VictoryPlugin;
 
 #[derive(Component)]
 pub struct VictoryUI;
 
+#[derive(Component, Clone, Copy)]
+enum VictoryButton {
+    NextLevel,
+    Retry,
+    Menu,
+}
+
+// A statistic that counts up from zero to its final value
+#[derive(Component)]
+struct CountUp {
+    target: f32,
+    value: CountValue,
+    timer: Timer,
+}
+
+#[derive(Clone, Copy)]
+enum CountValue {
+    // A whole number filled into the named argument
+    Arg(&'static str),
+    // A whole number that also picks the plural form
+    Count,
+    Time,
+}
+
+// Lights up after the count, one star after another
+#[derive(Component)]
+struct StarPip {
+    earned: bool,
+    timer: Timer,
+}
+
 impl Plugin for VictoryPlugin {
     fn build(&self, app: &mut App) {
         app
         .add_systems(
             OnEnter(InGameState::Victory),
-            setup_victory_ui,
-            // TODO sound
+            (setup_victory_ui.after(finish_run_timer), play_victory_sound),
         )
         .add_systems(
             Update,
-            handle_victory_input.run_if(in_state(InGameState::Victory)),
+            (handle_victory_input, count_up_statistics, reveal_stars)
+                .run_if(in_state(InGameState::Victory)),
         )
-        .add_systems(OnExit(InGameState::Victory), despawn_player_and_map);
+        .add_systems(
+            OnExit(InGameState::Victory),
+            (despawn_player_and_map, despawn_victory_ui),
+        );
     }
 }
 
-fn setup_victory_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
-    let font = asset_server.load("fonts/montserrat.ttf");
-    let text_style = TextStyle {
-        font: font.clone(),
-        font_size: 60.0,
-        color: Color::WHITE,
+fn play_victory_sound(mut play_sfx: EventWriter<PlaySfx>) {
+    play_sfx.send(PlaySfx(Sfx::Victory));
+}
+
+fn setup_victory_ui(
+    mut commands: Commands,
+    theme: Res<UiTheme>,
+    run_timer: Res<RunTimer>,
+    best_times: Res<BestTimes>,
+    context: Res<GameContext>,
+    tile_query: Query<&Tile>,
+) {
+    let level = LevelData::get(context.level);
+    let medals = &level.medals;
+    let medal = medals.medal_for(run_timer.elapsed);
+    let best_time = best_times
+        .0
+        .get(&context.level)
+        .copied()
+        .unwrap_or(run_timer.elapsed);
+    let stars = level.stars.stars_for(context.score);
+
+    // Hazards can't be activated, so they don't count towards the total
+    let total_tiles = tile_query.iter().filter(|tile| tile.kind.activates()).count();
+    let activated_tiles = tile_query.iter().filter(|tile| tile.activated).count();
+
+    let count_up = |target: f32, value: CountValue| CountUp {
+        target,
+        value,
+        timer: Timer::from_seconds(VICTORY_COUNT_UP_TIME, TimerMode::Once),
     };
 
-    commands
-        .spawn((
-            NodeBundle {
-                style: Style {
-                    width: Val::Percent(100.0),
-                    height: Val::Percent(100.0),
-                    flex_direction: FlexDirection::Column,
-                    justify_content: JustifyContent::Center,
-                    align_items: AlignItems::Center,
-                    ..default()
-                },
-                background_color: Color::srgba(0.0, 0.0, 0.0, 0.8).into(),
-                ..default()
-            },
-            VictoryUI,
-        ))
-        .with_children(|parent| {
-            parent.spawn(TextBundle::from_section("You Win!", text_style.clone()));
-            parent
-                .spawn(ButtonBundle {
-                    style: Style {
-                        width: Val::Px(250.0),
-                        height: Val::Px(65.0),
-                        border: UiRect::all(Val::Px(5.0)),
-                        padding: UiRect {
-                            left: Val::Px(10.0),
-                            right: Val::Px(10.0),
-                            top: Val::Px(5.0),
-                            bottom: Val::Px(5.0),
+    let background = Color::srgba(0.0, 0.0, 0.0, 0.8);
+    spawn_menu(&mut commands, &theme, background, VictoryUI, |menu| {
+        menu.title("victory.title");
+
+        menu.row(|row| {
+            for index in 0..MAX_STARS {
+                row.parent().spawn((
+                    NodeBundle {
+                        style: Style {
+                            width: Val::Px(40.0),
+                            height: Val::Px(40.0),
+                            border: UiRect::all(Val::Px(3.0)),
+                            ..default()
                         },
-                        justify_content: JustifyContent::Center,
-                        align_items: AlignItems::Center,
+                        border_color: Medal::Gold.color().into(),
+                        border_radius: BorderRadius::MAX,
+                        background_color: Color::srgb(0.2, 0.2, 0.2).into(),
                         ..default()
                     },
-                    border_color: BorderColor(Color::BLACK),
-                    border_radius: BorderRadius::MAX,
-                    background_color: NORMAL_BUTTON.into(),
-                    ..default()
-                })
-                .with_children(|parent| {
-                    parent.spawn(TextBundle::from_section("Restart", text_style.clone()));
-                });
+                    StarPip {
+                        earned: index < stars,
+                        timer: Timer::from_seconds(
+                            VICTORY_COUNT_UP_TIME + (index + 1) as f32 * VICTORY_STAR_INTERVAL,
+                            TimerMode::Once,
+                        ),
+                    },
+                ));
+            }
         });
+        menu.label(
+            LocalizedText::new("victory.stars")
+                .with_count(stars as i64)
+                .with_arg("max", MAX_STARS),
+            (),
+        );
+
+        menu.label(
+            LocalizedText::new("victory.score").with_arg("score", 0),
+            count_up(context.score as f32, CountValue::Arg("score")),
+        );
+        menu.label(
+            LocalizedText::new("victory.tiles")
+                .with_arg("activated", 0)
+                .with_arg("total", total_tiles),
+            count_up(activated_tiles as f32, CountValue::Arg("activated")),
+        );
+        menu.label(
+            LocalizedText::new("victory.deaths").with_count(0),
+            count_up(context.deaths as f32, CountValue::Count),
+        );
+        menu.label(
+            LocalizedText::new("victory.time").with_arg("time", format_time(0.0)),
+            count_up(run_timer.elapsed, CountValue::Time),
+        );
+
+        for (door, split) in run_timer.splits.iter().enumerate() {
+            menu.label(
+                LocalizedText::new("victory.split")
+                    .with_arg("door", door + 1)
+                    .with_arg("time", format_time(*split)),
+                (),
+            );
+        }
+        let medal_style = TextStyle {
+            color: medal.map_or(Color::WHITE, |medal| medal.color()),
+            ..menu.theme().text_style()
+        };
+        menu.text(
+            LocalizedText::new(medal.map_or("victory.no_medal", |medal| medal.key())),
+            medal_style,
+            (),
+        );
+        menu.label(
+            LocalizedText::new("victory.medal_times")
+                .with_arg("gold", format_time(medals.gold))
+                .with_arg("silver", format_time(medals.silver))
+                .with_arg("bronze", format_time(medals.bronze)),
+            (),
+        );
+        menu.label(
+            LocalizedText::new(if run_timer.personal_best {
+                "victory.new_best_time"
+            } else {
+                "victory.best_time"
+            })
+            .with_arg("time", format_time(best_time)),
+            (),
+        );
+        menu.label("victory.replay_hint", ());
+
+        menu.row(|row| {
+            if (context.level as usize) < LEVELS.len() {
+                row.button("victory.next_level", VictoryButton::NextLevel);
+            }
+            row.button("victory.retry", VictoryButton::Retry);
+            row.button("victory.menu", VictoryButton::Menu);
+        });
+    });
+}
+
+fn count_up_statistics(
+    time: Res<Time<Real>>,
+    mut query: Query<(&mut CountUp, &mut LocalizedText)>,
+) {
+    for (mut count_up, mut text) in query.iter_mut() {
+        if count_up.timer.finished() {
+            continue;
+        }
+        count_up.timer.tick(time.delta());
+        // Eases out, so the numbers slow down as they land
+        let progress = 1.0 - count_up.timer.fraction_remaining().powi(2);
+        let value = count_up.target * progress;
+        match count_up.value {
+            CountValue::Arg(name) => text.set_arg(name, value.round() as u32),
+            CountValue::Count => text.set_count(value.round() as i64),
+            CountValue::Time => text.set_arg("time", format_time(value)),
+        }
+    }
+}
+
+fn reveal_stars(
+    time: Res<Time<Real>>,
+    mut query: Query<(&mut StarPip, &mut BackgroundColor)>,
+    mut play_sfx: EventWriter<PlaySfx>,
+) {
+    for (mut pip, mut background) in query.iter_mut() {
+        if pip.timer.finished() {
+            continue;
+        }
+        pip.timer.tick(time.delta());
+        if pip.timer.just_finished() && pip.earned {
+            background.0 = Medal::Gold.color();
+            play_sfx.send(PlaySfx(Sfx::Click));
+        }
+    }
 }
 
 fn handle_victory_input(
-    mut commands: Commands,
+    mut activated: EventReader<WidgetActivated>,
+    button_query: Query<&VictoryButton>,
     mut in_game_state: ResMut<NextState<InGameState>>,
-    mut interaction_query: Query<
-        (&Interaction, &mut BackgroundColor),
-        (Changed<Interaction>, With<Button>),
-    >,
-    victory_ui: Query<Entity, With<VictoryUI>>,
+    mut app_state: ResMut<NextState<AppState>>,
     mut context: ResMut<GameContext>,
 ) {
-    for (interaction, mut color) in &mut interaction_query {
-        match *interaction {
-            Interaction::Pressed => {
-                // Restart the game
-                // Remove the game over UI
-                *color = PRESSED_BUTTON.into();
-                for entity in victory_ui.iter() {
-                    commands.entity(entity).despawn_recursive();
-                }
-
-                // Reset player lives
-                context.lives = PLAYER_LIVES;
+    for WidgetActivated(entity) in activated.read() {
+        let Ok(action) = button_query.get(*entity) else {
+            continue;
+        };
+        match action {
+            // The map and this screen are torn down on leaving the victory state
+            VictoryButton::NextLevel => {
+                context.level += 1;
                 in_game_state.set(InGameState::Reset);
             }
-            Interaction::Hovered => {
-                *color = HOVERED_BUTTON.into();
-            }
-            Interaction::None => {
-                *color = NORMAL_BUTTON.into();
+            VictoryButton::Retry => in_game_state.set(InGameState::Reset),
+            VictoryButton::Menu => {
+                in_game_state.set(InGameState::NotInGame);
+                app_state.set(AppState::Title);
             }
         }
     }
 }
+
+fn despawn_victory_ui(mut commands: Commands, victory_ui: Query<Entity, With<VictoryUI>>) {
+    for entity in victory_ui.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
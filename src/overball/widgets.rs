@@ -0,0 +1,726 @@
+use bevy::ecs::system::SystemId;
+use bevy::prelude::*;
+use bevy::ui::RelativeCursorPosition;
+
+use super::audio::{PlaySfx, Sfx};
+use super::constants::*;
+use super::high_scores::name_entry_active;
+use super::localization::LocalizedText;
+
+// Colors, sizes and the font every widget is built with
+#[derive(Resource, Clone)]
+pub struct UiTheme {
+    pub font: Handle<Font>,
+    pub text_color: Color,
+    pub title_size: f32,
+    pub button_text_size: f32,
+    pub text_size: f32,
+    pub button_width: f32,
+    pub button_height: f32,
+    pub button_normal: Color,
+    pub button_hovered: Color,
+    pub button_pressed: Color,
+    pub border_normal: Color,
+    pub border_focused: Color,
+    pub border_pressed: Color,
+    pub panel_background: Color,
+    pub slider_track: Color,
+    pub slider_fill: Color,
+}
+
+impl FromWorld for UiTheme {
+    fn from_world(world: &mut World) -> Self {
+        UiTheme::standard(world.resource::<AssetServer>().load("fonts/montserrat.ttf"))
+    }
+}
+
+impl UiTheme {
+    pub fn standard(font: Handle<Font>) -> Self {
+        UiTheme {
+            font,
+            text_color: Color::WHITE,
+            title_size: 60.0,
+            button_text_size: 36.0,
+            text_size: 24.0,
+            button_width: 300.0,
+            button_height: 60.0,
+            button_normal: NORMAL_BUTTON,
+            button_hovered: HOVERED_BUTTON,
+            button_pressed: PRESSED_BUTTON,
+            border_normal: Color::BLACK,
+            border_focused: Color::WHITE,
+            border_pressed: Color::srgb(1.0, 0.0, 0.0),
+            panel_background: Color::srgba(0.4, 0.4, 0.4, 0.5),
+            slider_track: Color::srgb(0.2, 0.2, 0.2),
+            slider_fill: Color::srgb(0.35, 0.75, 0.35),
+        }
+    }
+
+    // White on black with bright borders, so focus and edges stand out
+    pub fn high_contrast(font: Handle<Font>) -> Self {
+        UiTheme {
+            button_normal: Color::BLACK,
+            button_hovered: Color::srgb(0.15, 0.15, 0.15),
+            button_pressed: Color::srgb(0.3, 0.3, 0.3),
+            border_normal: Color::WHITE,
+            border_focused: Color::srgb(1.0, 1.0, 0.0),
+            border_pressed: Color::srgb(0.0, 1.0, 1.0),
+            panel_background: Color::BLACK,
+            slider_track: Color::BLACK,
+            slider_fill: Color::srgb(1.0, 1.0, 0.0),
+            ..UiTheme::standard(font)
+        }
+    }
+
+    pub fn title_style(&self) -> TextStyle {
+        self.text_style_sized(self.title_size)
+    }
+
+    pub fn button_text_style(&self) -> TextStyle {
+        self.text_style_sized(self.button_text_size)
+    }
+
+    pub fn text_style(&self) -> TextStyle {
+        self.text_style_sized(self.text_size)
+    }
+
+    pub fn text_style_sized(&self, font_size: f32) -> TextStyle {
+        TextStyle {
+            font: self.font.clone(),
+            font_size,
+            color: self.text_color,
+        }
+    }
+}
+
+// Widgets that arrow keys and the D-pad can move focus to
+#[derive(Component, Default)]
+pub struct Focusable;
+
+// Buttons and toggles, colored by the theme
+#[derive(Component)]
+pub struct WidgetButton;
+
+// 0 to 1, dragged with the mouse or stepped with left and right while focused
+#[derive(Component)]
+pub struct Slider {
+    pub value: f32,
+    pub step: f32,
+}
+
+#[derive(Component)]
+struct SliderFill;
+
+#[derive(Component)]
+struct Panel;
+
+#[derive(Component)]
+pub struct Toggle {
+    pub on: bool,
+    on_key: String,
+    off_key: String,
+}
+
+impl Toggle {
+    fn key(&self) -> &str {
+        if self.on {
+            &self.on_key
+        } else {
+            &self.off_key
+        }
+    }
+}
+
+#[derive(Component)]
+struct ToggleText;
+
+// Runs a one-shot system when the widget is activated, instead of handling `WidgetActivated`
+#[derive(Component)]
+pub struct OnActivate(pub SystemId);
+
+// A button or toggle was clicked, or Enter / A was pressed on it
+#[derive(Event, Debug, Clone, Copy)]
+pub struct WidgetActivated(pub Entity);
+
+#[derive(Event, Debug, Clone, Copy)]
+pub struct SliderChanged {
+    pub entity: Entity,
+    pub value: f32,
+}
+
+#[derive(Resource, Default)]
+pub struct UiFocus(pub Option<Entity>);
+
+#[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone)]
+pub struct WidgetSet;
+
+// Shared menu widgets with mouse, keyboard and gamepad support
+pub struct WidgetPlugin;
+
+impl Plugin for WidgetPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<UiTheme>()
+            .init_resource::<UiFocus>()
+            .add_event::<WidgetActivated>()
+            .add_event::<SliderChanged>()
+            .add_systems(
+                Update,
+                (
+                    (
+                        pointer_input,
+                        drag_sliders,
+                        // Arrows and Enter are for the name while typing one
+                        (navigation_input, activation_input).run_if(not(name_entry_active)),
+                    )
+                        .chain(),
+                    (
+                        style_buttons,
+                        restyle_for_theme.run_if(resource_changed::<UiTheme>),
+                        update_slider_fills,
+                        update_toggle_labels,
+                    ),
+                )
+                    .chain()
+                    .in_set(WidgetSet),
+            );
+    }
+}
+
+// Builds menus out of themed widgets: columns of titles, labels, buttons, sliders and toggles
+pub struct MenuBuilder<'a, 'b> {
+    parent: &'a mut ChildBuilder<'b>,
+    theme: &'a UiTheme,
+}
+
+// Full-screen, centered root for a menu
+pub fn spawn_menu(
+    commands: &mut Commands,
+    theme: &UiTheme,
+    background: Color,
+    root: impl Bundle,
+    build: impl FnOnce(&mut MenuBuilder),
+) -> Entity {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(16.0),
+                    ..default()
+                },
+                background_color: background.into(),
+                ..default()
+            },
+            root,
+        ))
+        .with_children(|parent| build(&mut MenuBuilder { parent, theme }))
+        .id()
+}
+
+impl<'b> MenuBuilder<'_, 'b> {
+    pub fn theme(&self) -> &UiTheme {
+        self.theme
+    }
+
+    // For content that isn't a widget
+    pub fn parent(&mut self) -> &mut ChildBuilder<'b> {
+        self.parent
+    }
+
+    pub fn title(&mut self, text: impl Into<LocalizedText>) -> Entity {
+        let text_style = self.theme.title_style();
+        self.text(text.into(), text_style, ())
+    }
+
+    pub fn label(&mut self, text: impl Into<LocalizedText>, extra: impl Bundle) -> Entity {
+        let text_style = self.theme.text_style();
+        self.text(text.into(), text_style, extra)
+    }
+
+    // Text that wraps rather than running off screen, since translations can be much longer
+    pub fn text(
+        &mut self,
+        text: LocalizedText,
+        text_style: TextStyle,
+        extra: impl Bundle,
+    ) -> Entity {
+        self.parent
+            .spawn((
+                TextBundle::from_section("", text_style)
+                    .with_text_justify(JustifyText::Center)
+                    .with_style(Style {
+                        max_width: Val::Vw(90.0),
+                        ..default()
+                    }),
+                text,
+                extra,
+            ))
+            .id()
+    }
+
+    pub fn button(&mut self, text: impl Into<LocalizedText>, extra: impl Bundle) -> Entity {
+        let size = Vec2::new(self.theme.button_width, self.theme.button_height);
+        let text_style = self.theme.button_text_style();
+        self.spawn_button(size, text.into(), text_style, extra, ())
+    }
+
+    // Square button sized for a symbol like + or -
+    pub fn small_button(&mut self, text: impl Into<LocalizedText>, extra: impl Bundle) -> Entity {
+        let size = Vec2::splat(self.theme.button_height * 0.7);
+        let text_style = self.theme.text_style();
+        self.spawn_button(size, text.into(), text_style, extra, ())
+    }
+
+    pub fn toggle(&mut self, on: bool, on_key: &str, off_key: &str, extra: impl Bundle) -> Entity {
+        let toggle = Toggle {
+            on,
+            on_key: on_key.to_string(),
+            off_key: off_key.to_string(),
+        };
+        let size = Vec2::new(self.theme.button_width, self.theme.button_height * 0.7);
+        let text = LocalizedText::new(toggle.key());
+        let text_style = self.theme.text_style();
+        self.spawn_button(size, text, text_style, (toggle, extra), ToggleText)
+    }
+
+    pub fn slider(
+        &mut self,
+        text: impl Into<LocalizedText>,
+        value: f32,
+        step: f32,
+        extra: impl Bundle,
+    ) -> Entity {
+        let theme = self.theme;
+        let mut slider = Entity::PLACEHOLDER;
+        self.row(|row| {
+            row.parent.spawn((
+                TextBundle::from_section("", theme.text_style()).with_style(Style {
+                    min_width: Val::Px(160.0),
+                    ..default()
+                }),
+                text.into(),
+            ));
+            slider = row
+                .parent
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(240.0),
+                            height: Val::Px(20.0),
+                            border: UiRect::all(Val::Px(2.0)),
+                            ..default()
+                        },
+                        border_color: theme.border_normal.into(),
+                        background_color: theme.slider_track.into(),
+                        ..default()
+                    },
+                    Slider {
+                        value: value.clamp(0.0, 1.0),
+                        step,
+                    },
+                    Focusable,
+                    RelativeCursorPosition::default(),
+                    extra,
+                ))
+                .with_children(|track| {
+                    track.spawn((
+                        NodeBundle {
+                            style: Style {
+                                width: Val::Percent(value.clamp(0.0, 1.0) * 100.0),
+                                height: Val::Percent(100.0),
+                                ..default()
+                            },
+                            background_color: theme.slider_fill.into(),
+                            ..default()
+                        },
+                        SliderFill,
+                    ));
+                })
+                .id();
+        });
+        slider
+    }
+
+    // Lays its widgets out side by side
+    pub fn row(&mut self, build: impl FnOnce(&mut MenuBuilder)) -> Entity {
+        let theme = self.theme;
+        self.parent
+            .spawn(NodeBundle {
+                style: Style {
+                    align_items: AlignItems::Center,
+                    column_gap: Val::Px(16.0),
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|parent| build(&mut MenuBuilder { parent, theme }))
+            .id()
+    }
+
+    // A column on the theme's panel background, swapped in and out with `show_panel`
+    pub fn panel(
+        &mut self,
+        visible: bool,
+        extra: impl Bundle,
+        build: impl FnOnce(&mut MenuBuilder),
+    ) -> Entity {
+        let theme = self.theme;
+        self.parent
+            .spawn((
+                NodeBundle {
+                    style: Style {
+                        display: if visible {
+                            Display::Flex
+                        } else {
+                            Display::None
+                        },
+                        padding: UiRect::all(Val::Px(20.0)),
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        row_gap: Val::Px(16.0),
+                        ..default()
+                    },
+                    background_color: theme.panel_background.into(),
+                    ..default()
+                },
+                Panel,
+                extra,
+            ))
+            .with_children(|parent| build(&mut MenuBuilder { parent, theme }))
+            .id()
+    }
+
+    fn spawn_button(
+        &mut self,
+        size: Vec2,
+        text: LocalizedText,
+        text_style: TextStyle,
+        extra: impl Bundle,
+        text_extra: impl Bundle,
+    ) -> Entity {
+        self.parent
+            .spawn((
+                ButtonBundle {
+                    // Grows to fit longer translations
+                    style: Style {
+                        min_width: Val::Px(size.x),
+                        min_height: Val::Px(size.y),
+                        padding: UiRect::axes(Val::Px(16.0), Val::Px(4.0)),
+                        border: UiRect::all(Val::Px(5.0)),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    border_color: self.theme.border_normal.into(),
+                    border_radius: BorderRadius::MAX,
+                    background_color: self.theme.button_normal.into(),
+                    ..default()
+                },
+                WidgetButton,
+                Focusable,
+                extra,
+            ))
+            .with_children(|parent| {
+                parent.spawn((
+                    TextBundle::from_section("", text_style).with_text_justify(JustifyText::Center),
+                    text,
+                    text_extra,
+                ));
+            })
+            .id()
+    }
+}
+
+// Shows the panel tagged `shown` and hides its siblings, moving focus into it on the next
+// arrow press
+pub fn show_panel<T: Component + PartialEq>(
+    panel_query: &mut Query<(&T, &mut Style)>,
+    shown: T,
+    focus: &mut UiFocus,
+) {
+    for (panel, mut style) in panel_query.iter_mut() {
+        style.display = if *panel == shown {
+            Display::Flex
+        } else {
+            Display::None
+        };
+    }
+    focus.0 = None;
+}
+
+fn activate(
+    entity: Entity,
+    commands: &mut Commands,
+    toggle_query: &mut Query<&mut Toggle>,
+    on_activate_query: &Query<&OnActivate>,
+    activated: &mut EventWriter<WidgetActivated>,
+    play_sfx: &mut EventWriter<PlaySfx>,
+) {
+    if let Ok(mut toggle) = toggle_query.get_mut(entity) {
+        toggle.on = !toggle.on;
+    }
+    if let Ok(OnActivate(system)) = on_activate_query.get(entity) {
+        commands.run_system(*system);
+    }
+    activated.send(WidgetActivated(entity));
+    play_sfx.send(PlaySfx(Sfx::Click));
+}
+
+#[allow(clippy::type_complexity)]
+fn pointer_input(
+    mut commands: Commands,
+    mut focus: ResMut<UiFocus>,
+    interaction_query: Query<
+        (Entity, &Interaction),
+        (Changed<Interaction>, With<Focusable>, Without<Slider>),
+    >,
+    mut toggle_query: Query<&mut Toggle>,
+    on_activate_query: Query<&OnActivate>,
+    mut activated: EventWriter<WidgetActivated>,
+    mut play_sfx: EventWriter<PlaySfx>,
+) {
+    for (entity, interaction) in interaction_query.iter() {
+        match *interaction {
+            Interaction::Pressed => {
+                focus.0 = Some(entity);
+                activate(
+                    entity,
+                    &mut commands,
+                    &mut toggle_query,
+                    &on_activate_query,
+                    &mut activated,
+                    &mut play_sfx,
+                );
+            }
+            // The mouse and the keyboard share one focus
+            Interaction::Hovered => focus.0 = Some(entity),
+            Interaction::None => {}
+        }
+    }
+}
+
+fn drag_sliders(
+    mut focus: ResMut<UiFocus>,
+    mut slider_query: Query<(Entity, &Interaction, &RelativeCursorPosition, &mut Slider)>,
+    mut changed: EventWriter<SliderChanged>,
+) {
+    for (entity, interaction, cursor, mut slider) in slider_query.iter_mut() {
+        // Follows the cursor for as long as the track is held, so it can be dragged
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        focus.0 = Some(entity);
+        if let Some(position) = cursor.normalized {
+            let value = position.x.clamp(0.0, 1.0);
+            if slider.value != value {
+                slider.value = value;
+                changed.send(SliderChanged { entity, value });
+            }
+        }
+    }
+}
+
+fn gamepad_just_pressed(
+    gamepads: &Gamepads,
+    buttons: &ButtonInput<GamepadButton>,
+    button_type: GamepadButtonType,
+) -> bool {
+    gamepads
+        .iter()
+        .any(|gamepad| buttons.just_pressed(GamepadButton::new(gamepad, button_type)))
+}
+
+fn navigation_input(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    mut focus: ResMut<UiFocus>,
+    focusable_query: Query<
+        (Entity, &Node, &GlobalTransform, &InheritedVisibility),
+        With<Focusable>,
+    >,
+    mut slider_query: Query<&mut Slider>,
+    mut changed: EventWriter<SliderChanged>,
+) {
+    let pressed = |key: KeyCode, button: GamepadButtonType| {
+        keyboard_input.just_pressed(key)
+            || gamepad_just_pressed(&gamepads, &gamepad_buttons, button)
+    };
+    let direction = if pressed(KeyCode::ArrowUp, GamepadButtonType::DPadUp) {
+        Vec2::NEG_Y
+    } else if pressed(KeyCode::ArrowDown, GamepadButtonType::DPadDown) {
+        Vec2::Y
+    } else if pressed(KeyCode::ArrowLeft, GamepadButtonType::DPadLeft) {
+        Vec2::NEG_X
+    } else if pressed(KeyCode::ArrowRight, GamepadButtonType::DPadRight) {
+        Vec2::X
+    } else {
+        return;
+    };
+
+    // Hidden panels lay out to zero size
+    let visible = |(entity, node, transform, visibility): (
+        Entity,
+        &Node,
+        &GlobalTransform,
+        &InheritedVisibility,
+    )| {
+        (visibility.get() && node.size().x > 0.0)
+            .then(|| (entity, transform.translation().truncate()))
+    };
+    let widgets: Vec<(Entity, Vec2)> = focusable_query.iter().filter_map(visible).collect();
+
+    let current = focus
+        .0
+        .and_then(|entity| widgets.iter().find(|(widget, _)| *widget == entity))
+        .copied();
+    let Some((current, position)) = current else {
+        // Nothing focused yet, start at the top
+        focus.0 = widgets
+            .iter()
+            .min_by(|(_, a), (_, b)| a.y.total_cmp(&b.y).then(a.x.total_cmp(&b.x)))
+            .map(|(entity, _)| *entity);
+        return;
+    };
+
+    if direction.y == 0.0 {
+        if let Ok(mut slider) = slider_query.get_mut(current) {
+            let value = (slider.value + slider.step * direction.x).clamp(0.0, 1.0);
+            if slider.value != value {
+                slider.value = value;
+                changed.send(SliderChanged {
+                    entity: current,
+                    value,
+                });
+            }
+            return;
+        }
+    }
+
+    // Closest widget in that direction, favouring ones in line with the current one
+    let score = |offset: Vec2| {
+        let along = offset.dot(direction);
+        let across = offset.perp_dot(direction).abs();
+        (along > 1.0).then_some(along + across * 2.0)
+    };
+    let next = widgets
+        .iter()
+        .filter_map(|(entity, other)| score(*other - position).map(|score| (*entity, score)))
+        .min_by(|(_, a), (_, b)| a.total_cmp(b));
+    if let Some((entity, _)) = next {
+        focus.0 = Some(entity);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn activation_input(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    focus: Res<UiFocus>,
+    focusable_query: Query<(), (With<Focusable>, Without<Slider>)>,
+    mut toggle_query: Query<&mut Toggle>,
+    on_activate_query: Query<&OnActivate>,
+    mut activated: EventWriter<WidgetActivated>,
+    mut play_sfx: EventWriter<PlaySfx>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::Enter)
+        && !gamepad_just_pressed(&gamepads, &gamepad_buttons, GamepadButtonType::South)
+    {
+        return;
+    }
+    if let Some(entity) = focus.0.filter(|entity| focusable_query.contains(*entity)) {
+        activate(
+            entity,
+            &mut commands,
+            &mut toggle_query,
+            &on_activate_query,
+            &mut activated,
+            &mut play_sfx,
+        );
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn style_buttons(
+    theme: Res<UiTheme>,
+    focus: Res<UiFocus>,
+    mut button_query: Query<
+        (Entity, &Interaction, &mut BackgroundColor, &mut BorderColor),
+        With<WidgetButton>,
+    >,
+    mut slider_query: Query<(Entity, &mut BorderColor), (With<Slider>, Without<WidgetButton>)>,
+) {
+    for (entity, interaction, mut background, mut border) in button_query.iter_mut() {
+        let (background_color, border_color) = if *interaction == Interaction::Pressed {
+            (theme.button_pressed, theme.border_pressed)
+        } else if focus.0 == Some(entity) {
+            (theme.button_hovered, theme.border_focused)
+        } else {
+            (theme.button_normal, theme.border_normal)
+        };
+        background.set_if_neq(background_color.into());
+        border.set_if_neq(border_color.into());
+    }
+    for (entity, mut border) in slider_query.iter_mut() {
+        let border_color = if focus.0 == Some(entity) {
+            theme.border_focused
+        } else {
+            theme.border_normal
+        };
+        border.set_if_neq(border_color.into());
+    }
+}
+
+// Backgrounds that are only set when spawned, for when the theme is swapped under an open menu
+#[allow(clippy::type_complexity)]
+fn restyle_for_theme(
+    theme: Res<UiTheme>,
+    mut panel_query: Query<&mut BackgroundColor, With<Panel>>,
+    mut track_query: Query<&mut BackgroundColor, (With<Slider>, Without<Panel>)>,
+    mut fill_query: Query<
+        &mut BackgroundColor,
+        (With<SliderFill>, Without<Slider>, Without<Panel>),
+    >,
+) {
+    for mut background in panel_query.iter_mut() {
+        background.set_if_neq(theme.panel_background.into());
+    }
+    for mut background in track_query.iter_mut() {
+        background.set_if_neq(theme.slider_track.into());
+    }
+    for mut background in fill_query.iter_mut() {
+        background.set_if_neq(theme.slider_fill.into());
+    }
+}
+
+fn update_slider_fills(
+    slider_query: Query<(&Slider, &Children), Changed<Slider>>,
+    mut fill_query: Query<&mut Style, With<SliderFill>>,
+) {
+    for (slider, children) in slider_query.iter() {
+        let mut fills = fill_query.iter_many_mut(children);
+        while let Some(mut style) = fills.fetch_next() {
+            style.width = Val::Percent(slider.value * 100.0);
+        }
+    }
+}
+
+fn update_toggle_labels(
+    toggle_query: Query<(&Toggle, &Children), Changed<Toggle>>,
+    mut text_query: Query<&mut LocalizedText, With<ToggleText>>,
+) {
+    for (toggle, children) in toggle_query.iter() {
+        let mut texts = text_query.iter_many_mut(children);
+        while let Some(mut text) = texts.fetch_next() {
+            if text.key != toggle.key() {
+                text.key = toggle.key().to_string();
+            }
+        }
+    }
+}
@@ -4,19 +4,11 @@ use bevy::prelude::*;
 use bevy::window::{CursorGrabMode, PrimaryWindow};
 
 /// Keeps track of mouse motion events, pitch, and yaw
-#[derive(Resource)]
+#[derive(Resource, Default)]
 struct InputState {
     reader_motion: ManualEventReader<MouseMotion>,
 }
 
-impl Default for InputState {
-    fn default() -> Self {
-        Self {
-            reader_motion: ManualEventReader::default(),
-        }
-    }
-}
-
 /// Mouse sensitivity and movement speed
 #[derive(Resource)]
 pub struct MovementSettings {
@@ -101,7 +93,8 @@ fn setup_player(mut commands: Commands) {
 /// Handles keyboard input and movement
 fn player_move(
     keys: Res<ButtonInput<KeyCode>>,
-    time: Res<Time>,
+    // Real time, so the camera can still fly around a paused or slowed down scene
+    time: Res<Time<Real>>,
     primary_window: Query<&Window, With<PrimaryWindow>>,
     settings: Res<MovementSettings>,
     key_bindings: Res<KeyBindings>,
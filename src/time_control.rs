@@ -0,0 +1,149 @@
+use bevy::prelude::*;
+use bevy::time::TimeSystem;
+use bevy_rapier3d::prelude::*;
+
+const MIN_TIME_SCALE: f32 = 0.125;
+const MAX_TIME_SCALE: f32 = 2.0;
+
+/// Pauses, slows down or single-steps the game clock and the rapier pipeline together.
+/// Anything driven by `Time` or the fixed schedule stops with it, `Time<Real>` keeps running.
+#[derive(Resource, Debug)]
+pub struct TimeControl {
+    pub paused: bool,
+    scale: f32,
+    step_requested: bool,
+}
+
+impl Default for TimeControl {
+    fn default() -> Self {
+        Self {
+            paused: false,
+            scale: 1.0,
+            step_requested: false,
+        }
+    }
+}
+
+impl TimeControl {
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Slow motion below 1, fast forward above
+    pub fn set_scale(&mut self, scale: f32) {
+        self.scale = scale.clamp(MIN_TIME_SCALE, MAX_TIME_SCALE);
+    }
+
+    /// Advances one fixed timestep on the next frame, only while paused
+    pub fn step(&mut self) {
+        if self.paused {
+            self.step_requested = true;
+        }
+    }
+}
+
+/// Debug keys for the time controls
+#[derive(Resource)]
+pub struct TimeControlBindings {
+    pub toggle_pause: KeyCode,
+    pub step: KeyCode,
+    pub slower: KeyCode,
+    pub faster: KeyCode,
+}
+
+impl Default for TimeControlBindings {
+    fn default() -> Self {
+        Self {
+            toggle_pause: KeyCode::KeyP,
+            step: KeyCode::Period,
+            slower: KeyCode::BracketLeft,
+            faster: KeyCode::BracketRight,
+        }
+    }
+}
+
+/// The debug key handling, so apps can turn it off while keys mean something else
+#[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone)]
+pub struct TimeControlInputSet;
+
+pub struct TimeControlPlugin;
+
+impl Plugin for TimeControlPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TimeControl>()
+            .init_resource::<TimeControlBindings>()
+            .add_systems(
+                First,
+                (
+                    apply_time_control.before(TimeSystem),
+                    step_time.after(TimeSystem),
+                ),
+            )
+            .add_systems(Update, time_control_input.in_set(TimeControlInputSet));
+    }
+}
+
+/// Handles the debug keys
+fn time_control_input(
+    keys: Res<ButtonInput<KeyCode>>,
+    bindings: Res<TimeControlBindings>,
+    mut time_control: ResMut<TimeControl>,
+) {
+    if keys.just_pressed(bindings.toggle_pause) {
+        time_control.paused = !time_control.paused;
+    }
+    if keys.just_pressed(bindings.step) {
+        time_control.step();
+    }
+    if keys.just_pressed(bindings.slower) {
+        let scale = time_control.scale / 2.0;
+        time_control.set_scale(scale);
+    }
+    if keys.just_pressed(bindings.faster) {
+        let scale = time_control.scale * 2.0;
+        time_control.set_scale(scale);
+    }
+}
+
+/// Runs before the clocks update, so pausing and scaling apply to this frame
+fn apply_time_control(
+    time_control: Res<TimeControl>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    rapier_config: Option<ResMut<RapierConfiguration>>,
+) {
+    if time_control.paused != virtual_time.is_paused() {
+        if time_control.paused {
+            virtual_time.pause();
+        } else {
+            virtual_time.unpause();
+        }
+    }
+    if virtual_time.relative_speed() != time_control.scale {
+        virtual_time.set_relative_speed(time_control.scale);
+    }
+
+    if let Some(mut rapier_config) = rapier_config {
+        let active = !time_control.paused || time_control.step_requested;
+        if rapier_config.physics_pipeline_active != active {
+            rapier_config.physics_pipeline_active = active;
+        }
+    }
+}
+
+/// Runs after the clocks update, so a step lands in this frame
+fn step_time(
+    mut time_control: ResMut<TimeControl>,
+    mut time: ResMut<Time>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    fixed_time: Res<Time<Fixed>>,
+) {
+    if !time_control.step_requested {
+        return;
+    }
+    time_control.step_requested = false;
+    if time_control.paused {
+        // Exactly one fixed tick, and a frame of the same length for everything else
+        virtual_time.advance_by(fixed_time.timestep());
+        *time = virtual_time.as_generic();
+    }
+}
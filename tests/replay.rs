@@ -0,0 +1,206 @@
+// A run that dies part way through plays back to the same place, however differently the
+// frames of the recording and the playback fall
+use std::time::Duration;
+
+use bevy::input::InputPlugin;
+use bevy::prelude::*;
+use bevy::scene::ScenePlugin;
+use bevy::state::app::StatesPlugin;
+use bevy::time::TimeUpdateStrategy;
+use bevy_rapier3d::prelude::*;
+use web_demos::overball::components::*;
+use web_demos::overball::constants::*;
+use web_demos::overball::death::DeathPlugin;
+use web_demos::overball::events::GameplayEventsPlugin;
+use web_demos::overball::hazards::HazardPlugin;
+use web_demos::overball::replay::{Replay, ReplayPlugin, ReplayState};
+use web_demos::overball::resources::*;
+use web_demos::overball::simulation::{InterpolatedTransform, SimulationPlugin};
+use web_demos::overball::states::*;
+use web_demos::overball::systems::check_player_out_of_bounds;
+
+// Ticks of play to compare, well past both deaths and respawns
+const CHECKED_TICKS: usize = 450;
+const MAX_FRAMES: usize = 5000;
+
+// Where the ball was after `CHECKED_TICKS` ticks of play
+#[derive(Resource, Default)]
+struct CheckedPosition(Option<Vec3>);
+
+// The gameplay the replay depends on, without rendering, windows or audio
+fn game_app() -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        TransformPlugin,
+        HierarchyPlugin,
+        StatesPlugin,
+        AssetPlugin::default(),
+        ScenePlugin,
+        InputPlugin,
+    ))
+    .init_asset::<Mesh>()
+    .init_asset::<StandardMaterial>()
+    .init_asset::<Font>()
+    .add_plugins(RapierPhysicsPlugin::<NoUserData>::default().in_fixed_schedule())
+    .add_plugins((
+        SimulationPlugin,
+        GameplayEventsPlugin,
+        HazardPlugin,
+        DeathPlugin,
+        ReplayPlugin,
+    ))
+    .insert_state(AppState::Game)
+    .init_state::<InGameState>()
+    .init_resource::<GameContext>()
+    .init_resource::<ControlSettings>()
+    .init_resource::<RespawnPoint>()
+    .init_resource::<RunTimer>()
+    .init_resource::<CheckedPosition>()
+    // Same as the game's gameplay sets
+    .configure_sets(
+        FixedUpdate,
+        GameplaySet::Input
+            .run_if(in_state(InGameState::Playing))
+            .run_if(no_pending_transition)
+            .before(PhysicsSet::SyncBackend),
+    )
+    .configure_sets(
+        FixedUpdate,
+        GameplaySet::Update
+            .run_if(in_state(InGameState::Playing))
+            .run_if(no_pending_transition)
+            .after(PhysicsSet::Writeback),
+    )
+    .add_systems(
+        OnEnter(InGameState::Reset),
+        spawn_level.in_set(GameplaySet::Setup),
+    )
+    .add_systems(
+        FixedUpdate,
+        (check_player_out_of_bounds, check_ball_position).in_set(GameplaySet::Update),
+    );
+
+    app.finish();
+    app.cleanup();
+    app.world_mut()
+        .resource_mut::<NextState<InGameState>>()
+        .set(InGameState::Reset);
+    app
+}
+
+// A floor with a hazard in the way of the ball rolling towards +x and an edge close by
+// towards -z
+fn spawn_level(mut commands: Commands, mut next_state: ResMut<NextState<InGameState>>) {
+    commands.spawn((
+        TransformBundle::from_transform(Transform::from_xyz(0.0, 0.0, 16.0)),
+        Collider::cuboid(10.0, 0.1, 20.0),
+    ));
+
+    let hazard_position = Vec3::new(3.0, 0.5, 0.0);
+    commands.spawn((
+        TransformBundle::from_transform(Transform::from_translation(hazard_position)),
+        RigidBody::KinematicPositionBased,
+        Collider::cuboid(0.5, 0.5, 0.5),
+        Sensor,
+        MovingHazard {
+            motion: HazardMotion::Spin { speed: 1.0 },
+            origin: hazard_position,
+            elapsed: 0.0,
+            next_waypoint: 0,
+        },
+    ));
+
+    let ball_properties = BallProperties::default();
+    commands.spawn((
+        SpatialBundle::from_transform(Transform::from_translation(ball_properties.position)),
+        Player,
+        Ball {
+            radius: ball_properties.radius,
+        },
+        RigidBody::Dynamic,
+        Collider::ball(ball_properties.radius * 2.0),
+        Friction::coefficient(BALL_FRICTION),
+        ColliderMassProperties::Mass(BALL_MASS),
+        Damping {
+            linear_damping: BALL_LINEAR_DAMPING,
+            angular_damping: BALL_ANGULAR_DAMPING,
+        },
+        Velocity::zero(),
+        ExternalForce::default(),
+        InterpolatedTransform::default(),
+    ));
+
+    next_state.set(InGameState::Playing);
+}
+
+fn check_ball_position(
+    replay_state: Res<ReplayState>,
+    player_query: Query<&Transform, With<Player>>,
+    mut checked: ResMut<CheckedPosition>,
+) {
+    let ticks = if replay_state.is_playback() {
+        replay_state.cursor
+    } else {
+        replay_state.replay.frames.len()
+    };
+    if ticks == CHECKED_TICKS && checked.0.is_none() {
+        checked.0 = player_query
+            .get_single()
+            .ok()
+            .map(|transform| transform.translation);
+    }
+}
+
+// Runs frames of the given lengths in turn until the checked tick, returning the ball's
+// position then
+fn run_until_checked(app: &mut App, frame_times: &[Duration], recording: bool) -> Vec3 {
+    for frame in 0..MAX_FRAMES {
+        let frame_time = frame_times[frame % frame_times.len()];
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(frame_time));
+
+        if recording {
+            // Bursts on the hazard, then falls off the floor, then rolls the other way
+            let key = match app.world().resource::<GameContext>().deaths {
+                0 => KeyCode::KeyD,
+                1 => KeyCode::KeyW,
+                _ => KeyCode::KeyS,
+            };
+            let mut keys = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+            keys.release_all();
+            keys.press(key);
+        }
+        app.update();
+
+        if let Some(position) = app.world().resource::<CheckedPosition>().0 {
+            return position;
+        }
+    }
+    panic!("The run never reached tick {}", CHECKED_TICKS);
+}
+
+#[test]
+fn replay_with_deaths_ends_where_the_run_did() {
+    let mut recording = game_app();
+    let recorded_position =
+        run_until_checked(&mut recording, &[Duration::from_secs_f64(1.0 / 60.0)], true);
+    assert_eq!(recording.world().resource::<GameContext>().deaths, 2);
+    let replay: Replay = recording.world().resource::<ReplayState>().replay.clone();
+
+    let mut playback = game_app();
+    playback
+        .world_mut()
+        .resource_mut::<ReplayState>()
+        .pending_playback = Some(replay);
+    // Uneven frames, so the death countdowns span a different number of ticks
+    let frame_times = [5, 23, 41, 12].map(Duration::from_millis);
+    let played_back_position = run_until_checked(&mut playback, &frame_times, false);
+    assert_eq!(playback.world().resource::<GameContext>().deaths, 2);
+
+    assert!(
+        recorded_position.distance(played_back_position) < 1e-4,
+        "recorded {} but played back {}",
+        recorded_position,
+        played_back_position
+    );
+}